@@ -0,0 +1,77 @@
+// Times the activation filters on a generated 4K screenshot-like image, checking each gives
+// identical output to difference_filter. Run with `--features parallel` to include
+// par_difference_filter and split brush_difference_filter's rows across threads.
+
+use std::time::{Duration, Instant};
+use image::{ImageBuffer, Rgb, RgbImage};
+use nea_element_detection::activation::{
+    activation_map, brush_difference_filter, difference_filter, get_pixel_activation, IMMEDIATE_NEIGHBOUR_WEIGHT
+};
+
+const WIDTH: u32 = 3840;
+const HEIGHT: u32 = 2160;
+const RUNS: u32 = 3;
+
+// Flat panels with outlined boxes and a noisy strip, so both flat and busy areas are timed
+fn get_test_image() -> RgbImage {
+    return RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        if y > HEIGHT - 200 {
+            let noise = (x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503)) >> 8;
+            return Rgb([noise as u8, (noise >> 8) as u8, (noise >> 16) as u8])
+        }
+        let (box_x, box_y) = (x % 240, y % 120);
+        if (box_x == 20 || box_x == 200) && (20..=80).contains(&box_y)
+            || (box_y == 20 || box_y == 80) && (20..=200).contains(&box_x) {
+            return Rgb([40, 40, 40])
+        }
+        return Rgb([((x / 240) * 20) as u8, 230, 230])
+    })
+}
+
+// Fastest of RUNS runs, with the output of the last
+fn time(filter: impl Fn() -> activation_map) -> (Duration, activation_map) {
+    let mut fastest = Duration::MAX;
+    let mut output = activation_map::new(0, 0);
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        output = filter();
+        fastest = fastest.min(start.elapsed());
+    }
+    return (fastest, output)
+}
+
+fn main() {
+    let image = get_test_image();
+    let difference_function = |buffer: &ImageBuffer<Rgb<u8>, Vec<u8>>, x, y| {
+        get_pixel_activation(buffer, x, y, IMMEDIATE_NEIGHBOUR_WEIGHT)
+    };
+
+    let (serial_time, serial_output) = time(|| difference_filter(&image, &difference_function));
+    println!("difference_filter        {}x{}: {:?}", WIDTH, HEIGHT, serial_time);
+
+    let (brush_time, brush_output) = time(|| brush_difference_filter(&image, IMMEDIATE_NEIGHBOUR_WEIGHT));
+    println!(
+        "brush_difference_filter  {}x{}: {:?} ({:.1}x)",
+        WIDTH,
+        HEIGHT,
+        brush_time,
+        serial_time.as_secs_f64() / brush_time.as_secs_f64()
+    );
+    assert!(brush_output == serial_output, "brush output differs from difference_filter");
+
+    #[cfg(feature = "parallel")]
+    {
+        use nea_element_detection::activation::par_difference_filter;
+        let (parallel_time, parallel_output) = time(|| par_difference_filter(&image, &difference_function));
+        println!(
+            "par_difference_filter    {}x{}: {:?} ({:.1}x, {} threads)",
+            WIDTH,
+            HEIGHT,
+            parallel_time,
+            serial_time.as_secs_f64() / parallel_time.as_secs_f64(),
+            rayon::current_num_threads()
+        );
+        assert!(parallel_output == serial_output, "parallel output differs from serial");
+    }
+    println!("outputs are identical");
+}
@@ -0,0 +1,331 @@
+use image::{ImageBuffer, Pixel};
+
+pub struct activation_stats {
+    pub max: f32,
+    pub min: f32,
+    pub activation_count: u32, // No. pixels with activation > 0
+    pub avg_activation: f32, // Avg. activation for pixels with activation > 0
+    pub median_activation: f32, // Of pixels with activation > 0
+    pub std_activation: f32, // Standard deviation of pixels with activation > 0
+    // No. pixels with activation > 0 in each bin of HISTOGRAM_BIN_WIDTH, bin i covers
+    // i * width up to (i + 1) * width. Unactivated pixels are left out, otherwise they
+    // outnumber everything else and get_otsu_threshold only splits zero from non-zero.
+    pub histogram: Vec<u32>,
+}
+
+pub const HISTOGRAM_BINS: usize = 256;
+pub const HISTOGRAM_BIN_WIDTH: f32 = MAX_ACTIVATION / HISTOGRAM_BINS as f32;
+
+// Bin of activation_stats::histogram an activation falls in
+pub fn get_histogram_bin(activation: f32) -> usize {
+    return ((activation / HISTOGRAM_BIN_WIDTH) as usize).min(HISTOGRAM_BINS - 1)
+}
+
+// Largest activation get_pixel_activation can give, every channel differing by 255
+pub const MAX_ACTIVATION: f32 = 255.0;
+
+// Activation of every pixel, as found by difference_filter. Kept as f32 rather than an
+// image so no precision is lost before get_lines thresholds it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct activation_map {
+    width: u32,
+    height: u32,
+    values: Vec<f32>, // Row major
+}
+
+impl activation_map {
+    pub fn new(width: u32, height: u32) -> activation_map {
+        return activation_map { width, height, values: vec![0.0; width as usize * height as usize] }
+    }
+
+    pub fn width(&self) -> u32 {
+        return self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        return self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> f32 {
+        return self.values[self.get_index(x, y)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, activation: f32) {
+        let index = self.get_index(x, y);
+        self.values[index] = activation;
+    }
+
+    pub fn values(&self) -> &[f32] {
+        return &self.values
+    }
+
+    fn get_index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "({}, {}) is outside the {}x{} map", x, y, self.width, self.height);
+        return y as usize * self.width as usize + x as usize
+    }
+
+    // Stretches the activations so the largest is MAX_ACTIVATION, so thresholds mean the
+    // same on low contrast images. Does nothing if nothing is activated.
+    pub fn normalise(&mut self) {
+        let max = self.values.iter().cloned().fold(0.0, f32::max);
+        if max <= 0.0 {
+            return
+        }
+        for value in self.values.iter_mut() {
+            *value *= MAX_ACTIVATION / max;
+        }
+    }
+}
+
+pub const IMMEDIATE_NEIGHBOUR_WEIGHT: f32 = 0.6; // Describes how immediate and unnimedate activation should impact overall
+// activation relative to each other see get_pixel_activation
+
+// Whether a brush offset is in the outer ring of neighbours rather than touching the pixel
+fn is_unimmediate(x_offset: i64, y_offset: i64) -> bool {
+    return x_offset.abs() == 2 || y_offset.abs() == 2
+}
+
+// Mean absolute difference of each channel, so 255 at most
+pub fn get_channel_difference(a: &[u8], b: &[u8]) -> f32 {
+    let mut difference: f32 = 0.0;
+    for color in 0..a.len() {
+        difference += ((a[color] as i32 - b[color] as i32) as f32).abs() / a.len() as f32; // Adjusts for no. channels
+    }
+    return difference
+}
+
+// Mixes the total differences to the immediate and unimmediate neighbours into one activation
+fn get_brush_mix(
+    immediate_activation: f32,
+    checked_no_immediate: usize,
+    unimmediate_activation: f32,
+    checked_no_unimmediate: usize,
+    immediate_neighbour_weight: f32
+) -> f32 {
+    let immediate = immediate_activation / checked_no_immediate as f32;
+    let unimmediate = unimmediate_activation / checked_no_unimmediate as f32;
+    return immediate * immediate_neighbour_weight + unimmediate * (1.0-immediate_neighbour_weight)
+}
+
+// Creates a sort of brush, where immediate neighbours have more of an effect on the
+// activation of the pixel at (x, y), and their neighbours have a slight effect.
+// get_difference(neighbour_x, neighbour_y) is how much a neighbour differs from the pixel.
+pub fn get_brush_activation(
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    immediate_neighbour_weight: f32,
+    get_difference: impl Fn(u32, u32) -> f32
+) -> f32 {
+    let mut immediate_activation = 0.0; // Immediate neighbour's total activation
+    let mut unimmediate_activation = 0.0; // All other activation
+    let mut checked_no_immediate: usize = 0;
+    let mut checked_no_unimmediate: usize = 0;
+    for x_offset in -2..=2 {
+        for y_offset in -2..=2 {
+            if x_offset == 0 && y_offset == 0 {
+                continue
+            }
+            let offseted_x = (x as i64) + x_offset;
+            let offseted_y = (y as i64) + y_offset;
+            if offseted_x < 0
+                || offseted_x >= width as i64
+                || offseted_y < 0
+                || offseted_y >= height as i64 {
+                continue
+            }
+            let difference = get_difference(offseted_x as u32, offseted_y as u32);
+            if is_unimmediate(x_offset, y_offset) {
+                unimmediate_activation += difference;
+                checked_no_unimmediate += 1;
+            } else {
+                immediate_activation += difference;
+                checked_no_immediate += 1;
+            }
+        }
+    }
+    return get_brush_mix(
+        immediate_activation,
+        checked_no_immediate,
+        unimmediate_activation,
+        checked_no_unimmediate,
+        immediate_neighbour_weight
+    )
+}
+
+pub fn get_pixel_activation(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    x: u32,
+    y: u32,
+    immediate_neighbour_weight: f32
+) -> f32 {
+    let pixel_channels = buffer.get_pixel(x, y).channels();
+    return get_brush_activation(x, y, buffer.width(), buffer.height(), immediate_neighbour_weight, |offseted_x, offseted_y| {
+        get_channel_difference(pixel_channels, buffer.get_pixel(offseted_x, offseted_y).channels())
+    })
+}
+
+// Any function giving the activation of the pixel at (x, y), see get_pixel_activation.
+// Sync so rows can be filtered on separate threads, see par_difference_filter
+pub type difference_function = dyn Fn(&ImageBuffer<image::Rgb<u8>, Vec<u8>>, u32, u32) -> f32 + Sync;
+
+pub fn difference_filter(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    difference_function: &difference_function
+) -> activation_map {
+    // Creates an "activation map" which will store the values of
+    // the "activation" - how each pixel compares to its surroundings
+    let mut activation_map = activation_map::new(buffer.width(), buffer.height());
+    for (x, y, _) in buffer.enumerate_pixels() {
+        activation_map.set(x, y, difference_function(buffer, x, y));
+    }
+    return activation_map
+}
+
+// Same as difference_filter, with rows split across threads. Each pixel is still found by
+// a single call to difference_function, so the output is identical.
+#[cfg(feature = "parallel")]
+pub fn par_difference_filter(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    difference_function: &difference_function
+) -> activation_map {
+    use rayon::prelude::*;
+
+    let mut activation_map = activation_map::new(buffer.width(), buffer.height());
+    let row_length = buffer.width() as usize;
+    if row_length == 0 {
+        return activation_map
+    }
+    activation_map.values.par_chunks_mut(row_length).enumerate().for_each(|(y, row)| {
+        for (x, activation) in row.iter_mut().enumerate() {
+            *activation = difference_function(buffer, x as u32, y as u32);
+        }
+    });
+    return activation_map
+}
+
+// Activation of every pixel in row y, written to activations. Gives exactly the same values
+// as get_pixel_activation, but works a whole row at a time: for each offset in the brush the
+// row is compared against the shifted row, so the inner loop is a straight run over slices
+// the compiler can vectorise. Offsets are added in the same order as get_pixel_activation
+// so the floating point sums round the same way.
+pub fn get_row_activations(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    y: u32,
+    immediate_neighbour_weight: f32,
+    activations: &mut [f32]
+) {
+    let width = buffer.width() as usize;
+    let height = buffer.height() as i64;
+    let row_length = width * 3;
+    let samples: &[u8] = buffer.as_raw();
+    let row = &samples[y as usize * row_length..(y as usize + 1) * row_length];
+
+    let mut immediate_activation = vec![0.0_f32; width];
+    let mut unimmediate_activation = vec![0.0_f32; width];
+    for x_offset in -2_i64..=2 {
+        // Columns whose offset neighbour is inside the image
+        let start = (-x_offset).max(0) as usize;
+        let end = (width as i64 - x_offset.max(0)).max(start as i64) as usize;
+        for y_offset in -2_i64..=2 {
+            let offseted_y = y as i64 + y_offset;
+            if (x_offset == 0 && y_offset == 0) || offseted_y < 0 || offseted_y >= height || start >= end {
+                continue
+            }
+            let offseted_row_start = offseted_y as usize * row_length;
+            let offseted_row = &samples[offseted_row_start..offseted_row_start + row_length];
+            let offseted_start = (start as i64 + x_offset) as usize;
+            let centres = row[start * 3..end * 3].chunks_exact(3);
+            let neighbours = offseted_row[offseted_start * 3..(offseted_start + end - start) * 3].chunks_exact(3);
+            let totals = if is_unimmediate(x_offset, y_offset) {
+                &mut unimmediate_activation[start..end]
+            } else {
+                &mut immediate_activation[start..end]
+            };
+            for ((total, centre), neighbour) in totals.iter_mut().zip(centres).zip(neighbours) {
+                *total += get_channel_difference(centre, neighbour);
+            }
+        }
+    }
+
+    // Neighbours inside the image, for the averages
+    let get_span = |centre: i64, radius: i64, size: i64| (centre + radius).min(size - 1) - (centre - radius).max(0) + 1;
+    let rows_near = get_span(y as i64, 1, height);
+    let rows_far = get_span(y as i64, 2, height);
+    for (x, activation) in activations.iter_mut().enumerate().take(width) {
+        let checked_no_immediate = get_span(x as i64, 1, width as i64) * rows_near - 1;
+        let checked_no_unimmediate = get_span(x as i64, 2, width as i64) * rows_far - 1 - checked_no_immediate;
+        *activation = get_brush_mix(
+            immediate_activation[x],
+            checked_no_immediate as usize,
+            unimmediate_activation[x],
+            checked_no_unimmediate as usize,
+            immediate_neighbour_weight
+        );
+    }
+}
+
+// Same output as difference_filter with get_pixel_activation, using get_row_activations.
+// Rows are split across threads when the parallel feature is on.
+pub fn brush_difference_filter(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    immediate_neighbour_weight: f32
+) -> activation_map {
+    let mut activation_map = activation_map::new(buffer.width(), buffer.height());
+    let row_length = buffer.width() as usize;
+    if row_length == 0 {
+        return activation_map
+    }
+    let filter_row = |(y, row): (usize, &mut [f32])| {
+        get_row_activations(buffer, y as u32, immediate_neighbour_weight, row);
+    };
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        activation_map.values.par_chunks_mut(row_length).enumerate().for_each(filter_row);
+    }
+    #[cfg(not(feature = "parallel"))]
+    activation_map.values.chunks_mut(row_length).enumerate().for_each(filter_row);
+    return activation_map
+}
+
+pub fn get_activation_stats(activation_map: &activation_map) -> activation_stats {
+    // Calculates useful statistics for a given map, see activation_stats
+    let mut max: f32 = 0.0;
+    let mut min: f32 = MAX_ACTIVATION;
+    let mut activated: Vec<f32> = Vec::new();
+    let mut histogram: Vec<u32> = vec![0; HISTOGRAM_BINS];
+
+    // NaN (pixels with no neighbours) is skipped
+    for activation in activation_map.values().iter().filter(|activation| !activation.is_nan()) {
+        max = max.max(*activation);
+        min = min.min(*activation);
+        if *activation > 0.0 {
+            histogram[get_histogram_bin(*activation)] += 1;
+            activated.push(*activation)
+        }
+    }
+    let activation_count = activated.len() as u32;
+    let total_activation: f64 = activated.iter().map(|activation| *activation as f64).sum();
+    let avg_activation: f32 = (total_activation / activation_count as f64) as f32;
+    let variance: f64 = activated.iter()
+        .map(|activation| (*activation as f64 - avg_activation as f64).powi(2))
+        .sum::<f64>() / activation_count as f64;
+
+    activated.sort_by(f32::total_cmp);
+    let median_activation = match activated.len() {
+        0 => f32::NAN,
+        count if count % 2 == 0 => (activated[count / 2 - 1] + activated[count / 2]) / 2.0,
+        count => activated[count / 2],
+    };
+    return activation_stats{
+        max,
+        min,
+        activation_count,
+        avg_activation,
+        median_activation,
+        std_activation: variance.sqrt() as f32,
+        histogram,
+    }
+}
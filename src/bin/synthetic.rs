@@ -0,0 +1,90 @@
+use std::{fs, path::PathBuf};
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use nea_element_detection::synthetic::{generate_screenshot, ground_truth_image, synthetic_settings};
+
+#[derive(Parser)]
+#[command(about = "Generates synthetic UI screenshots with ground truth element boxes")]
+struct args {
+    /// Directory the images and their JSON are written to
+    #[arg(short, long, default_value = "synthetic")]
+    output_dir: PathBuf,
+
+    /// Number of screenshots to generate
+    #[arg(short, long, default_value_t = 100)]
+    count: usize,
+
+    /// Seed for the random layouts, the same seed always gives the same screenshots
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    #[arg(long, default_value_t = synthetic_settings::default().width)]
+    width: u32,
+
+    #[arg(long, default_value_t = synthetic_settings::default().height)]
+    height: u32,
+
+    /// Most elements drawn in each screenshot, fewer fit if they are large
+    #[arg(long, default_value_t = synthetic_settings::default().max_elements)]
+    max_elements: usize,
+
+    /// Mean font size in pixels, sizes are normally distributed around it
+    #[arg(long, default_value_t = synthetic_settings::default().font_size_mean)]
+    font_size_mean: f32,
+
+    #[arg(long, default_value_t = synthetic_settings::default().font_size_std)]
+    font_size_std: f32,
+
+    /// Largest standard deviation of the gaussian noise added to each screenshot
+    #[arg(long, default_value_t = synthetic_settings::default().max_noise_std)]
+    max_noise_std: f32,
+
+    /// Chance of each screenshot having a dark background, 0 - 1
+    #[arg(long, default_value_t = synthetic_settings::default().dark_theme_chance)]
+    dark_theme_chance: f64,
+}
+
+impl args {
+    fn get_settings(&self) -> synthetic_settings {
+        return synthetic_settings {
+            width: self.width,
+            height: self.height,
+            max_elements: self.max_elements,
+            font_size_mean: self.font_size_mean,
+            font_size_std: self.font_size_std,
+            max_noise_std: self.max_noise_std,
+            dark_theme_chance: self.dark_theme_chance,
+        }
+    }
+}
+
+fn run(args: args) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = args.get_settings();
+    settings.validate()?;
+    fs::create_dir_all(&args.output_dir)?;
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    for i in 0..args.count {
+        let (buffer, elements) = generate_screenshot(&mut rng, &settings);
+        let name = format!("synthetic_{:04}", i);
+        let image_name = name.clone() + ".png";
+        buffer.save(args.output_dir.join(&image_name))?;
+        let ground_truth = ground_truth_image {
+            path: image_name,
+            width: buffer.width(),
+            height: buffer.height(),
+            elements,
+        };
+        fs::write(args.output_dir.join(name + ".json"), serde_json::to_string_pretty(&ground_truth)?)?;
+    }
+    eprintln!("Wrote {} screenshots to {}", args.count, args.output_dir.display());
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run(args::parse()) {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+// Axis-aligned box in image coordinates: (x, y) is the top left pixel, y grows downwards.
+// width and height count pixels, so a single pixel is 1x1 and right/bottom are inside the box.
+// A box covers at least one pixel: new panics on a width or height of 0 and deserializing
+// rejects one. The fields are public, so code building a rect by hand must keep to this too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "rect_fields")]
+pub struct rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// A rect as written, before its size is checked
+#[derive(Deserialize)]
+struct rect_fields {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl TryFrom<rect_fields> for rect {
+    type Error = String;
+
+    fn try_from(fields: rect_fields) -> Result<rect, String> {
+        if fields.width == 0 || fields.height == 0 {
+            return Err(format!("box at ({}, {}) is {}x{}, expected at least 1x1", fields.x, fields.y, fields.width, fields.height))
+        }
+        return Ok(rect::new(fields.x, fields.y, fields.width, fields.height))
+    }
+}
+
+impl rect {
+    // width and height must be at least 1
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> rect {
+        assert!(width > 0 && height > 0, "box at ({}, {}) is {}x{}, expected at least 1x1", x, y, width, height);
+        return rect { x, y, width, height }
+    }
+
+    // Smallest box containing every point, None if there are none
+    pub fn from_points(points: &[(u32, u32)]) -> Option<rect> {
+        let first = points.first()?;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (first.0, first.1, first.0, first.1);
+        for point in points {
+            min_x = min_x.min(point.0);
+            min_y = min_y.min(point.1);
+            max_x = max_x.max(point.0);
+            max_y = max_y.max(point.1);
+        }
+        return Some(rect::new(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+    }
+
+    // Last column inside the box
+    pub fn right(&self) -> u32 {
+        return self.x + self.width - 1
+    }
+
+    // Last row inside the box, see right
+    pub fn bottom(&self) -> u32 {
+        return self.y + self.height - 1
+    }
+
+    pub fn area(&self) -> u32 {
+        return self.width * self.height
+    }
+
+    pub fn top_left(&self) -> (u32, u32) {
+        return (self.x, self.y)
+    }
+
+    pub fn top_right(&self) -> (u32, u32) {
+        return (self.right(), self.y)
+    }
+
+    pub fn bottom_left(&self) -> (u32, u32) {
+        return (self.x, self.bottom())
+    }
+
+    pub fn bottom_right(&self) -> (u32, u32) {
+        return (self.right(), self.bottom())
+    }
+
+    pub fn contains_point(&self, point: (u32, u32)) -> bool {
+        return point.0 >= self.x
+            && point.1 >= self.y
+            && point.0 <= self.right()
+            && point.1 <= self.bottom()
+    }
+
+    // True if other is entirely within this box (including being the same box)
+    pub fn contains(&self, other: &rect) -> bool {
+        return other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    // Smallest box containing both
+    pub fn union(&self, other: &rect) -> rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        return rect::new(x, y, right - x + 1, bottom - y + 1)
+    }
+
+    // Overlap of the two boxes, None if they don't overlap
+    pub fn intersection(&self, other: &rect) -> Option<rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right < x || bottom < y {
+            return None
+        }
+        return Some(rect::new(x, y, right - x + 1, bottom - y + 1))
+    }
+
+    // Intersection over union, 0.0 for disjoint boxes and 1.0 for identical ones
+    pub fn get_iou(&self, other: &rect) -> f32 {
+        let intersection_area = self.intersection(other).map_or(0, |intersection| intersection.area());
+        let union_area = self.area() + other.area() - intersection_area;
+        return intersection_area as f32 / union_area as f32
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lines::line;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum element_kind {
+    Button,
+    TextField,
+    Checkbox,
+    RadioButton,
+    Icon,
+    Image,
+    TextLabel,
+    Separator,
+    Container,
+}
+
+impl element_kind {
+    // Name as it would be spoken, e.g. "text field"
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            element_kind::Button => "button",
+            element_kind::TextField => "text field",
+            element_kind::Checkbox => "checkbox",
+            element_kind::RadioButton => "radio button",
+            element_kind::Icon => "icon",
+            element_kind::Image => "image",
+            element_kind::TextLabel => "text label",
+            element_kind::Separator => "separator",
+            element_kind::Container => "container",
+        }
+    }
+
+    // Accepts the spoken name or the snake case one used in JSON, e.g. "text_field"
+    pub fn from_name(name: &str) -> Option<element_kind> {
+        let name = name.trim().to_lowercase().replace(['_', '-'], " ");
+        return ELEMENT_KINDS.into_iter().find(|kind| kind.get_name() == name)
+    }
+}
+
+pub const ELEMENT_KINDS: [element_kind; 9] = [
+    element_kind::Button,
+    element_kind::TextField,
+    element_kind::Checkbox,
+    element_kind::RadioButton,
+    element_kind::Icon,
+    element_kind::Image,
+    element_kind::TextLabel,
+    element_kind::Separator,
+    element_kind::Container,
+];
+
+// Score every kind must beat, stops weak guesses getting high confidence just because
+// every other kind scored zero
+const UNKNOWN_SCORE: f32 = 0.1;
+const CORNER_SIZE: f32 = 0.2; // Fraction of each side counted as a corner, see corner_fill
+
+// Everything the classifier looks at for a single line
+pub struct element_features {
+    pub width: u32,
+    pub height: u32,
+    pub aspect: f32, // Width / height
+    pub fill_ratio: f32, // See line::get_activation
+    pub corner_fill: f32, // Fraction of the 4 bounding box corners with pixels in, 0 for circles
+    pub color_count: usize, // Colours found by get_line_colors
+    pub is_text: bool, // Found by get_text_lines
+    pub child_count: usize, // Lines entirely inside this one
+    pub text_child_count: usize,
+    pub parent_count: usize, // Lines this one is entirely inside
+}
+
+// True if inner's bounding box is within outer's, and they aren't the same box
+pub fn is_inside(inner: &line, outer: &line) -> bool {
+    return inner.bounds != outer.bounds && outer.bounds.contains(&inner.bounds);
+}
+
+// A corner is the triangle cut off by a diagonal from CORNER_SIZE along one side to
+// CORNER_SIZE down the other. Circles pass their bounding box's corners about 0.3 of the
+// way along each side, so leave these triangles empty, unlike square corners.
+fn get_corner_fill(line: &line) -> f32 {
+    let bounds = line.bounds;
+    let (x0, y0, x1, y1) = (bounds.x, bounds.y, bounds.right(), bounds.bottom());
+    let corner_width = bounds.width as f32 * CORNER_SIZE;
+    let corner_height = bounds.height as f32 * CORNER_SIZE;
+    let mut corners_filled = [false; 4];
+    for (x, y) in &line.pixels {
+        // How far in from each side, in corner sizes
+        let left = (*x - x0) as f32 / corner_width;
+        let right = (x1 - *x) as f32 / corner_width;
+        let top = (*y - y0) as f32 / corner_height;
+        let bottom = (y1 - *y) as f32 / corner_height;
+        for (i, corner_distance) in [left + top, right + top, left + bottom, right + bottom].iter().enumerate() {
+            if *corner_distance < 1.0 {
+                corners_filled[i] = true;
+            }
+        }
+    }
+    return corners_filled.iter().filter(|filled| **filled).count() as f32 / 4.0;
+}
+
+// color_counts and is_text are per line, in the same order as lines
+pub fn get_elements_features(lines: &[line], color_counts: &[usize], is_text: &[bool]) -> Vec<element_features> {
+    let mut features: Vec<element_features> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let width = line.bounds.width;
+        let height = line.bounds.height;
+        let mut child_count = 0;
+        let mut text_child_count = 0;
+        let mut parent_count = 0;
+        for (j, other) in lines.iter().enumerate() {
+            if is_inside(other, line) {
+                child_count += 1;
+                if is_text[j] {
+                    text_child_count += 1;
+                }
+            } else if is_inside(line, other) {
+                parent_count += 1;
+            }
+        }
+        features.push(element_features {
+            width,
+            height,
+            aspect: width as f32 / height as f32,
+            fill_ratio: line.get_activation(),
+            corner_fill: get_corner_fill(line),
+            color_count: color_counts[i],
+            is_text: is_text[i],
+            child_count,
+            text_child_count,
+            parent_count,
+        });
+    }
+    return features;
+}
+
+// 0.0 at or below low, 1.0 at or above high, linear in between
+fn ramp(value: f32, low: f32, high: f32) -> f32 {
+    return ((value - low) / (high - low)).clamp(0.0, 1.0);
+}
+
+// 1.0 between the inner bounds, falling to 0.0 at the outer bounds
+fn band(value: f32, outer_low: f32, inner_low: f32, inner_high: f32, outer_high: f32) -> f32 {
+    return ramp(value, outer_low, inner_low) * (1.0 - ramp(value, inner_high, outer_high));
+}
+
+// How well the features fit a kind, 0.0 - 1.0. Each factor is one trait of the kind,
+// multiplied so any trait missing rules the kind out.
+fn get_kind_score(kind: element_kind, features: &element_features) -> f32 {
+    let width = features.width as f32;
+    let height = features.height as f32;
+    let shorter_side = width.min(height);
+    let longer_side = width.max(height);
+    let squareness = band(features.aspect, 0.6, 0.85, 1.15, 1.6);
+    let hollow = 1.0 - ramp(features.fill_ratio, 0.35, 0.7); // Outlines rather than solid shapes
+    let has_text = if features.text_child_count > 0 { 1.0 } else { 0.0 };
+    // get_text_lines takes any line of exactly two colours as text, so a plain shape on a
+    // plain background looks like text. It is treated as a hint rather than ruling kinds out.
+    let not_text = if features.is_text { 0.5 } else { 1.0 };
+    let no_children = 1.0 - ramp(features.child_count as f32, 0.0, 1.0);
+    return match kind {
+        element_kind::Button => not_text
+            * band(features.aspect, 0.8, 1.5, 8.0, 14.0)
+            * band(height, 12.0, 20.0, 64.0, 96.0)
+            * hollow
+            * (0.3 + 0.7 * has_text),
+        element_kind::TextField => not_text
+            * ramp(features.aspect, 3.0, 6.0)
+            * band(height, 12.0, 20.0, 56.0, 80.0)
+            * hollow
+            * ramp(features.corner_fill, 0.25, 0.75)
+            * (1.0 - 0.6 * has_text),
+        element_kind::Checkbox => not_text
+            * squareness
+            * band(longer_side, 6.0, 10.0, 28.0, 40.0)
+            * ramp(features.corner_fill, 0.5, 1.0)
+            * hollow,
+        element_kind::RadioButton => not_text
+            * squareness
+            * band(longer_side, 6.0, 10.0, 28.0, 40.0)
+            * (1.0 - ramp(features.corner_fill, 0.0, 0.5))
+            * hollow,
+        element_kind::Icon => not_text
+            * band(features.aspect, 0.4, 0.7, 1.4, 2.5)
+            * band(longer_side, 8.0, 12.0, 48.0, 72.0)
+            * ramp(features.fill_ratio, 0.3, 0.5)
+            * (1.0 - ramp(features.child_count as f32, 2.0, 5.0)),
+        element_kind::Image => not_text
+            * ramp(shorter_side, 32.0, 64.0)
+            * ramp(features.fill_ratio, 0.4, 0.6)
+            * ramp(features.color_count as f32, 3.0, 8.0),
+        element_kind::TextLabel => if features.is_text {
+            0.8 * band(height, 3.0, 5.0, 48.0, 72.0) * no_children
+        } else {
+            // Text get_text_lines missed, e.g. anti-aliased, still looks like a short wide solid run
+            0.5 * band(height, 4.0, 6.0, 32.0, 48.0)
+                * ramp(features.aspect, 0.8, 2.0)
+                * ramp(features.fill_ratio, 0.35, 0.5)
+                * no_children
+        },
+        element_kind::Separator => not_text
+            * (1.0 - ramp(shorter_side, 3.0, 6.0))
+            * ramp(longer_side / shorter_side, 6.0, 12.0)
+            * ramp(features.fill_ratio, 0.5, 0.8),
+        element_kind::Container => not_text
+            * ramp(longer_side, 64.0, 128.0)
+            * ramp(shorter_side, 24.0, 48.0)
+            * hollow
+            * ramp(features.child_count as f32, 0.0, 2.0),
+    };
+}
+
+// Returns the kind the features fit best, and how confident that is (0.0 - 1.0)
+pub fn classify(features: &element_features) -> (element_kind, f32) {
+    let mut best_kind = element_kind::Icon;
+    let mut best_score = 0.0;
+    let mut total_score = UNKNOWN_SCORE;
+    for kind in ELEMENT_KINDS {
+        let score = get_kind_score(kind, features);
+        total_score += score;
+        if score > best_score {
+            best_kind = kind;
+            best_score = score;
+        }
+    }
+    if best_score == 0.0 {
+        // Nothing fits, so guess from size alone
+        let longer_side = std::cmp::max(features.width, features.height);
+        let kind = if longer_side < 64 { element_kind::Icon } else { element_kind::Container };
+        return (kind, 0.0);
+    }
+    return (best_kind, best_score / total_score);
+}
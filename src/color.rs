@@ -0,0 +1,248 @@
+use image::{ImageBuffer, Pixel};
+use serde::{Deserialize, Serialize};
+
+use crate::activation::{activation_map, get_brush_activation, get_channel_difference};
+
+// How the difference between two colours is measured. Raw RGB differences make low
+// contrast edges which are still clearly visible (e.g. blue on purple) look weak, the
+// perceptual ones match what a person sees more closely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum color_difference {
+    Rgb, // The original comparisons, see get_pixel_activation and get_color_distance
+    Cie76, // Euclidean distance in CIELAB (Delta E 1976)
+    Ciede2000, // CIELAB Delta E 2000, corrects Cie76 for blues and greys
+    Oklab, // Euclidean distance in OKLab
+}
+
+pub const COLOR_DIFFERENCES: [color_difference; 4] = [
+    color_difference::Rgb,
+    color_difference::Cie76,
+    color_difference::Ciede2000,
+    color_difference::Oklab,
+];
+
+impl color_difference {
+    // Name used in configuration, e.g. "ciede2000"
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            color_difference::Rgb => "rgb",
+            color_difference::Cie76 => "cie76",
+            color_difference::Ciede2000 => "ciede2000",
+            color_difference::Oklab => "oklab",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<color_difference> {
+        let name = name.trim().to_lowercase().replace(['-', '_'], "");
+        return COLOR_DIFFERENCES.into_iter().find(|difference| difference.get_name() == name)
+    }
+
+    // Largest difference get_color_difference gives any two sRGB colours, rounded up. Rgb is
+    // black to white, Cie76 blue to green and Ciede2000 dark blue to yellow green (found by
+    // searching), while Oklab is scaled so black to white is its largest.
+    pub fn get_max_difference(&self) -> f32 {
+        return match self {
+            color_difference::Rgb => 442.0,
+            color_difference::Cie76 => 660.0,
+            color_difference::Ciede2000 => 305.0,
+            color_difference::Oklab => 256.0,
+        }
+    }
+}
+
+impl std::fmt::Display for color_difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.get_name())
+    }
+}
+
+impl std::str::FromStr for color_difference {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<color_difference, String> {
+        return color_difference::from_name(name).ok_or_else(|| {
+            let names: Vec<&str> = COLOR_DIFFERENCES.iter().map(|difference| difference.get_name()).collect();
+            format!("unknown colour difference \"{}\", expected one of: {}", name, names.join(", "))
+        })
+    }
+}
+
+// Perceptual differences are scaled so black to white is 255, the same as the largest
+// activation, so existing thresholds stay meaningful. Delta E runs 0 - 100 and OKLab 0 - 1.
+const DELTA_E_SCALE: f32 = 2.55;
+const OKLAB_SCALE: f32 = 255.0;
+
+// D65 white point, for CIELAB
+const WHITE_X: f32 = 0.95047;
+const WHITE_Y: f32 = 1.0;
+const WHITE_Z: f32 = 1.08883;
+
+fn srgb_to_linear(channel: u8) -> f32 {
+    let channel = channel as f32 / 255.0;
+    if channel <= 0.04045 {
+        return channel / 12.92
+    }
+    return ((channel + 0.055) / 1.055).powf(2.4)
+}
+
+fn get_linear_rgb(color: &image::Rgb<u8>) -> [f32; 3] {
+    return [srgb_to_linear(color[0]), srgb_to_linear(color[1]), srgb_to_linear(color[2])]
+}
+
+// CIELAB (L, a, b) of an sRGB colour, L is 0 - 100
+#[allow(clippy::excessive_precision)] // Matrices are kept as published
+pub fn rgb_to_lab(color: &image::Rgb<u8>) -> [f32; 3] {
+    let [r, g, b] = get_linear_rgb(color);
+    let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / WHITE_X;
+    let y = (0.2126729 * r + 0.7151522 * g + 0.0721750 * b) / WHITE_Y;
+    let z = (0.0193339 * r + 0.1191920 * g + 0.9503041 * b) / WHITE_Z;
+    let f = |t: f32| {
+        if t > 216.0 / 24389.0 {
+            return t.cbrt()
+        }
+        return (24389.0 / 27.0 * t + 16.0) / 116.0
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    return [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+// OKLab (L, a, b) of an sRGB colour, L is 0 - 1
+#[allow(clippy::excessive_precision)]
+pub fn rgb_to_oklab(color: &image::Rgb<u8>) -> [f32; 3] {
+    let [r, g, b] = get_linear_rgb(color);
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    return [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+fn get_euclidean_distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    return ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+// CIEDE2000 difference between two CIELAB colours, following Sharma, Wu and Dalal (2005)
+pub fn get_delta_e2000(lab_1: &[f32; 3], lab_2: &[f32; 3]) -> f32 {
+    let [l_1, a_1, b_1] = lab_1.map(|value| value as f64);
+    let [l_2, a_2, b_2] = lab_2.map(|value| value as f64);
+    let chroma_mean = (a_1.hypot(b_1) + a_2.hypot(b_2)) / 2.0;
+    let g = 0.5 * (1.0 - (chroma_mean.powi(7) / (chroma_mean.powi(7) + 25f64.powi(7))).sqrt());
+    let (a_1, a_2) = (a_1 * (1.0 + g), a_2 * (1.0 + g));
+    let (c_1, c_2) = (a_1.hypot(b_1), a_2.hypot(b_2));
+    let get_hue = |a: f64, b: f64| {
+        if a == 0.0 && b == 0.0 {
+            return 0.0
+        }
+        return b.atan2(a).to_degrees().rem_euclid(360.0)
+    };
+    let (h_1, h_2) = (get_hue(a_1, b_1), get_hue(a_2, b_2));
+
+    let delta_l = l_2 - l_1;
+    let delta_c = c_2 - c_1;
+    let mut delta_h = h_2 - h_1;
+    if c_1 * c_2 == 0.0 {
+        delta_h = 0.0;
+    } else if delta_h > 180.0 {
+        delta_h -= 360.0;
+    } else if delta_h < -180.0 {
+        delta_h += 360.0;
+    }
+    let delta_big_h = 2.0 * (c_1 * c_2).sqrt() * (delta_h.to_radians() / 2.0).sin();
+
+    let l_mean = (l_1 + l_2) / 2.0;
+    let c_mean = (c_1 + c_2) / 2.0;
+    let h_mean = if c_1 * c_2 == 0.0 {
+        h_1 + h_2
+    } else if (h_1 - h_2).abs() <= 180.0 {
+        (h_1 + h_2) / 2.0
+    } else if h_1 + h_2 < 360.0 {
+        (h_1 + h_2 + 360.0) / 2.0
+    } else {
+        (h_1 + h_2 - 360.0) / 2.0
+    };
+    let t = 1.0 - 0.17 * (h_mean - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_mean).to_radians().cos()
+        + 0.32 * (3.0 * h_mean + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_mean - 63.0).to_radians().cos();
+    let delta_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+    let r_c = 2.0 * (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt();
+    let s_l = 1.0 + 0.015 * (l_mean - 50.0).powi(2) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * c_mean;
+    let s_h = 1.0 + 0.015 * c_mean * t;
+    let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+    let l_term = delta_l / s_l;
+    let c_term = delta_c / s_c;
+    let h_term = delta_big_h / s_h;
+    return (l_term * l_term + c_term * c_term + h_term * h_term + r_t * c_term * h_term).sqrt() as f32
+}
+
+// A colour converted once into the space a color_difference compares in
+fn convert(color: &image::Rgb<u8>, color_difference: color_difference) -> [f32; 3] {
+    return match color_difference {
+        color_difference::Rgb => [color[0] as f32, color[1] as f32, color[2] as f32],
+        color_difference::Cie76 | color_difference::Ciede2000 => rgb_to_lab(color),
+        color_difference::Oklab => rgb_to_oklab(color),
+    }
+}
+
+// Difference between two colours already passed through convert, see get_color_difference
+fn get_converted_difference(a: &[f32; 3], b: &[f32; 3], color_difference: color_difference) -> f32 {
+    return match color_difference {
+        color_difference::Rgb => get_euclidean_distance(a, b),
+        color_difference::Cie76 => get_euclidean_distance(a, b) * DELTA_E_SCALE,
+        color_difference::Ciede2000 => get_delta_e2000(a, b) * DELTA_E_SCALE,
+        color_difference::Oklab => get_euclidean_distance(a, b) * OKLAB_SCALE,
+    }
+}
+
+// Euclidean distance between two colours in RGB space
+pub fn get_color_distance(a: &image::Rgb<u8>, b: &image::Rgb<u8>) -> f32 {
+    let mut difference_squared: f32 = 0.0;
+    for channel in 0..a.channels().len() {
+        difference_squared += 
+            (a[channel] as i32 - b[channel] as i32).pow(2) as f32;
+    }
+    return difference_squared.sqrt();
+}
+
+// Difference between two colours. Rgb is get_color_distance, the perceptual differences
+// are scaled so black to white is 255.
+pub fn get_color_difference(a: &image::Rgb<u8>, b: &image::Rgb<u8>, color_difference: color_difference) -> f32 {
+    if color_difference == color_difference::Rgb {
+        return get_color_distance(a, b)
+    }
+    return get_converted_difference(&convert(a, color_difference), &convert(b, color_difference), color_difference)
+}
+
+// The brush of get_pixel_activation, with differences measured by color_difference.
+// Each pixel is converted once up front, as the conversions are too slow to repeat for
+// every neighbour. Rgb gives get_pixel_activation's mean channel difference.
+pub fn perceptual_difference_filter(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    immediate_neighbour_weight: f32,
+    color_difference: color_difference
+) -> activation_map {
+    let (width, height) = (buffer.width(), buffer.height());
+    let converted: Vec<[f32; 3]> = buffer.pixels().map(|pixel| convert(pixel, color_difference)).collect();
+    let get_converted = |x: u32, y: u32| &converted[y as usize * width as usize + x as usize];
+
+    let mut activation_map = activation_map::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let centre = get_converted(x, y);
+            let activation = get_brush_activation(x, y, width, height, immediate_neighbour_weight, |offseted_x, offseted_y| {
+                if color_difference == color_difference::Rgb {
+                    return get_channel_difference(buffer.get_pixel(x, y).channels(), buffer.get_pixel(offseted_x, offseted_y).channels())
+                }
+                return get_converted_difference(centre, get_converted(offseted_x, offseted_y), color_difference)
+            });
+            activation_map.set(x, y, activation);
+        }
+    }
+    return activation_map
+}
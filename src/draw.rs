@@ -0,0 +1,74 @@
+use image::ImageBuffer;
+
+use crate::lines::line;
+
+pub fn draw_line(mut buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>>, line: &line)
+    -> ImageBuffer::<image::Rgb<u8>, Vec<u8>> {
+        for point in 0..line.pixels.len() {
+            let x = line.pixels[point].0;
+            let y = line.pixels[point].1;
+            let pixel = buffer.get_pixel_mut(x, y);
+            *pixel = image::Rgb([255,255,255]);
+        }
+        return buffer
+    }
+
+pub fn draw_bounding_box(mut buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>>, line: &line) 
+    -> ImageBuffer::<image::Rgb<u8>, Vec<u8>> {
+    let bounds = line.bounds;
+    for x in bounds.x..=bounds.right() {
+        *buffer.get_pixel_mut(x, bounds.y) = image::Rgb([0,255,0]);
+        *buffer.get_pixel_mut(x, bounds.bottom()) = image::Rgb([0,255,0]);
+    }
+    for y in bounds.y..=bounds.bottom() {
+        *buffer.get_pixel_mut(bounds.x, y) = image::Rgb([0,255,0]);
+        *buffer.get_pixel_mut(bounds.right(), y) = image::Rgb([0,255,0]);
+    }
+    return buffer
+}
+
+// Draws the line's rotated bounds in blue, if add_rotated_bounds has been called.
+// Corners are continuous coordinates, so edges are stepped along one pixel at a time.
+pub fn draw_rotated_box(mut buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>>, line: &line)
+    -> ImageBuffer::<image::Rgb<u8>, Vec<u8>> {
+    let Some(rotated_bounds) = line.rotated_bounds else {
+        return buffer
+    };
+    let corners = rotated_bounds.corners();
+    for i in 0..corners.len() {
+        let start = corners[i];
+        let end = corners[(i + 1) % corners.len()];
+        let steps = (end.0 - start.0).abs().max((end.1 - start.1).abs()).ceil().max(1.0) as u32;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            // Corners lie on pixel edges, the pixel just inside is the one drawn
+            let x = (start.0 + (end.0 - start.0) * t - 0.5).round();
+            let y = (start.1 + (end.1 - start.1) * t - 0.5).round();
+            if x >= 0.0 && y >= 0.0 && (x as u32) < buffer.width() && (y as u32) < buffer.height() {
+                *buffer.get_pixel_mut(x as u32, y as u32) = image::Rgb([0,0,255]);
+            }
+        }
+    }
+    return buffer
+}
+
+// Crops the line's bounding box out of the image
+pub fn get_line_image(line: &line, image_buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>)
+    -> ImageBuffer<image::Rgb<u8>, Vec<u8>> {
+    // The drawn box from draw_bounding_box is the border of this crop
+    return image::imageops::crop_imm(
+        image_buffer,
+        line.bounds.x,
+        line.bounds.y,
+        line.bounds.width,
+        line.bounds.height
+    ).to_image();
+}
+
+pub fn get_line_images(lines: &[line], image_buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>) -> Vec<ImageBuffer<image::Rgb<u8>, Vec<u8>>> {
+    let mut image_lines: Vec<ImageBuffer<image::Rgb<u8>, Vec<u8>>> = Vec::new();
+    for line in lines {
+        image_lines.push(get_line_image(line, image_buffer));
+    }
+    return image_lines;
+}
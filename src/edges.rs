@@ -0,0 +1,318 @@
+use image::{ImageBuffer, Pixel};
+use serde::{Deserialize, Serialize};
+
+use crate::activation::{activation_map, brush_difference_filter, difference_filter};
+use crate::color::{color_difference, perceptual_difference_filter};
+use crate::lines::get_surrounding_pixels;
+
+// Ways of finding activation, so they can be compared on the same screenshots.
+// All give activations on the same scale as get_pixel_activation: a sharp step between
+// two flat colours gives roughly the mean channel difference across it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum edge_operator {
+    Brush, // get_pixel_activation, the original 5x5 brush
+    Sobel,
+    Scharr,
+    LaplacianOfGaussian, // Zero crossings of the blurred image's Laplacian
+    Canny, // Thin edges, see get_canny_activations
+}
+
+pub const EDGE_OPERATORS: [edge_operator; 5] = [
+    edge_operator::Brush,
+    edge_operator::Sobel,
+    edge_operator::Scharr,
+    edge_operator::LaplacianOfGaussian,
+    edge_operator::Canny,
+];
+
+impl edge_operator {
+    // Name used in configuration, e.g. "laplacian_of_gaussian"
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            edge_operator::Brush => "brush",
+            edge_operator::Sobel => "sobel",
+            edge_operator::Scharr => "scharr",
+            edge_operator::LaplacianOfGaussian => "laplacian_of_gaussian",
+            edge_operator::Canny => "canny",
+        }
+    }
+
+    // Also accepts "log" for LaplacianOfGaussian
+    pub fn from_name(name: &str) -> Option<edge_operator> {
+        let name = name.trim().to_lowercase().replace('-', "_");
+        if name == "log" {
+            return Some(edge_operator::LaplacianOfGaussian)
+        }
+        return EDGE_OPERATORS.into_iter().find(|operator| operator.get_name() == name)
+    }
+}
+
+impl std::fmt::Display for edge_operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.get_name())
+    }
+}
+
+impl std::str::FromStr for edge_operator {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<edge_operator, String> {
+        return edge_operator::from_name(name).ok_or_else(|| {
+            let names: Vec<&str> = EDGE_OPERATORS.iter().map(|operator| operator.get_name()).collect();
+            format!("unknown edge operator \"{}\", expected one of: {}", name, names.join(", "))
+        })
+    }
+}
+
+pub const BLUR_SIGMA: f32 = 1.0; // Gaussian blur before LaplacianOfGaussian and Canny
+// Canny keeps edges above the high threshold, and weaker edges down to the low threshold
+// joined to them
+pub const CANNY_LOW_THRESHOLD: f32 = 10.0;
+pub const CANNY_HIGH_THRESHOLD: f32 = 30.0;
+
+// Sum of the positive weights in each kernel, so a step of height h gives about h
+const SOBEL_SCALE: f32 = 4.0;
+const SCHARR_SCALE: f32 = 16.0;
+// A blurred step gives a smaller Laplacian than a sharp one, found by measuring a step
+// of 255 at BLUR_SIGMA
+const LAPLACIAN_OF_GAUSSIAN_SCALE: f32 = 0.31;
+
+// Settings for the operators which have them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct edge_settings {
+    pub immediate_neighbour_weight: f32, // Brush, see get_pixel_activation
+    pub color_difference: color_difference, // Brush only, the others compare intensity
+    pub blur_sigma: f32,
+    pub canny_low_threshold: f32,
+    pub canny_high_threshold: f32,
+}
+
+// Activation of every pixel using the chosen operator
+pub fn edge_filter(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    operator: edge_operator,
+    settings: &edge_settings
+) -> activation_map {
+    return match operator {
+        // Same output as difference_filter with get_pixel_activation, but faster
+        edge_operator::Brush if settings.color_difference == color_difference::Rgb => {
+            brush_difference_filter(buffer, settings.immediate_neighbour_weight)
+        },
+        edge_operator::Brush => perceptual_difference_filter(
+            buffer,
+            settings.immediate_neighbour_weight,
+            settings.color_difference
+        ),
+        edge_operator::Sobel => difference_filter(buffer, &get_sobel_activation),
+        edge_operator::Scharr => difference_filter(buffer, &get_scharr_activation),
+        edge_operator::LaplacianOfGaussian => get_laplacian_of_gaussian_activations(buffer, settings.blur_sigma),
+        edge_operator::Canny => get_canny_activations(
+            buffer,
+            settings.blur_sigma,
+            settings.canny_low_threshold,
+            settings.canny_high_threshold
+        ),
+    }
+}
+
+// Mean of the channels, so flat colours give the same scale as get_pixel_activation.
+// Coordinates outside the image use the nearest edge pixel.
+fn get_intensity(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>, x: i64, y: i64) -> f32 {
+    let x = x.clamp(0, buffer.width() as i64 - 1) as u32;
+    let y = y.clamp(0, buffer.height() as i64 - 1) as u32;
+    let channels = buffer.get_pixel(x, y).channels();
+    return channels.iter().map(|channel| *channel as f32).sum::<f32>() / channels.len() as f32
+}
+
+fn get_intensity_map(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>) -> activation_map {
+    return difference_filter(buffer, &|buffer, x, y| get_intensity(buffer, x as i64, y as i64))
+}
+
+// Value at (x, y), using the nearest edge value outside the map
+fn get_clamped(map: &activation_map, x: i64, y: i64) -> f32 {
+    return map.get(
+        x.clamp(0, map.width() as i64 - 1) as u32,
+        y.clamp(0, map.height() as i64 - 1) as u32
+    )
+}
+
+// (x gradient, y gradient) from a 3x3 kernel with the given edge and centre weights,
+// [edge, centre, edge] across and [-1, 0, 1] along the gradient
+fn get_gradient(get_value: impl Fn(i64, i64) -> f32, x: i64, y: i64, edge_weight: f32, centre_weight: f32) -> (f32, f32) {
+    let weights = [edge_weight, centre_weight, edge_weight];
+    let mut x_gradient = 0.0;
+    let mut y_gradient = 0.0;
+    for (i, weight) in weights.iter().enumerate() {
+        let offset = i as i64 - 1;
+        x_gradient += weight * (get_value(x + 1, y + offset) - get_value(x - 1, y + offset));
+        y_gradient += weight * (get_value(x + offset, y + 1) - get_value(x + offset, y - 1));
+    }
+    return (x_gradient, y_gradient)
+}
+
+pub fn get_sobel_activation(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>, x: u32, y: u32) -> f32 {
+    let (x_gradient, y_gradient) = get_gradient(
+        |x, y| get_intensity(buffer, x, y), x as i64, y as i64, 1.0, 2.0
+    );
+    return x_gradient.hypot(y_gradient) / SOBEL_SCALE
+}
+
+// Like Sobel, with weights giving a more even response to edges at any angle
+pub fn get_scharr_activation(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>, x: u32, y: u32) -> f32 {
+    let (x_gradient, y_gradient) = get_gradient(
+        |x, y| get_intensity(buffer, x, y), x as i64, y as i64, 3.0, 10.0
+    );
+    return x_gradient.hypot(y_gradient) / SCHARR_SCALE
+}
+
+// Separable Gaussian blur, does nothing if sigma is 0 or below
+pub fn gaussian_blur(map: &activation_map, sigma: f32) -> activation_map {
+    if sigma <= 0.0 || map.width() == 0 || map.height() == 0 {
+        return map.clone()
+    }
+    let radius = (sigma * 3.0).ceil() as i64;
+    let mut kernel: Vec<f32> = (-radius..=radius)
+        .map(|offset| (-(offset * offset) as f32 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|weight| *weight /= total);
+
+    let mut horizontal = activation_map::new(map.width(), map.height());
+    let mut blurred = activation_map::new(map.width(), map.height());
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            let value = kernel.iter().enumerate()
+                .map(|(i, weight)| weight * get_clamped(map, x as i64 + i as i64 - radius, y as i64))
+                .sum();
+            horizontal.set(x, y, value);
+        }
+    }
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            let value = kernel.iter().enumerate()
+                .map(|(i, weight)| weight * get_clamped(&horizontal, x as i64, y as i64 + i as i64 - radius))
+                .sum();
+            blurred.set(x, y, value);
+        }
+    }
+    return blurred
+}
+
+// Activation at zero crossings of the Laplacian of the blurred image, 0 elsewhere.
+// Only the positive side of each crossing is marked, so edges are a single pixel wide.
+pub fn get_laplacian_of_gaussian_activations(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    sigma: f32
+) -> activation_map {
+    let blurred = gaussian_blur(&get_intensity_map(buffer), sigma);
+    let mut laplacian = activation_map::new(buffer.width(), buffer.height());
+    for y in 0..buffer.height() as i64 {
+        for x in 0..buffer.width() as i64 {
+            let value = get_clamped(&blurred, x - 1, y)
+                + get_clamped(&blurred, x + 1, y)
+                + get_clamped(&blurred, x, y - 1)
+                + get_clamped(&blurred, x, y + 1)
+                - 4.0 * get_clamped(&blurred, x, y);
+            laplacian.set(x as u32, y as u32, value);
+        }
+    }
+
+    let mut activations = activation_map::new(buffer.width(), buffer.height());
+    for y in 0..buffer.height() as i64 {
+        for x in 0..buffer.width() as i64 {
+            let value = get_clamped(&laplacian, x, y);
+            if value <= 0.0 {
+                continue
+            }
+            let mut strength: f32 = 0.0;
+            for (x_offset, y_offset) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let neighbour = get_clamped(&laplacian, x + x_offset, y + y_offset);
+                if neighbour < 0.0 {
+                    strength = strength.max(value - neighbour);
+                }
+            }
+            activations.set(x as u32, y as u32, strength / LAPLACIAN_OF_GAUSSIAN_SCALE);
+        }
+    }
+    return activations
+}
+
+// Canny edge detection: Sobel on the blurred image, thinned to the ridge of each edge by
+// non-maximum suppression, then hysteresis keeps edges above high_threshold and any
+// weaker ones down to low_threshold connected to them. Kept pixels have their gradient
+// magnitude as activation, the rest 0.
+pub fn get_canny_activations(
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    sigma: f32,
+    low_threshold: f32,
+    high_threshold: f32
+) -> activation_map {
+    let (width, height) = (buffer.width(), buffer.height());
+    let blurred = gaussian_blur(&get_intensity_map(buffer), sigma);
+    let mut magnitudes = activation_map::new(width, height);
+    let mut directions: Vec<(i64, i64)> = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let (x_gradient, y_gradient) = get_gradient(
+                |x, y| get_clamped(&blurred, x, y), x as i64, y as i64, 1.0, 2.0
+            );
+            magnitudes.set(x, y, x_gradient.hypot(y_gradient) / SOBEL_SCALE);
+            // Gradient direction rounded to the nearest 45 degrees, as a step to a neighbour
+            let angle = y_gradient.atan2(x_gradient).to_degrees().rem_euclid(180.0);
+            directions.push(match angle {
+                angle if !(22.5..157.5).contains(&angle) => (1, 0),
+                angle if angle < 67.5 => (1, 1),
+                angle if angle < 112.5 => (0, 1),
+                _ => (-1, 1),
+            });
+        }
+    }
+
+    // Non-maximum suppression
+    let mut thinned = activation_map::new(width, height);
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let magnitude = magnitudes.get(x as u32, y as u32);
+            let (x_step, y_step) = directions[(y * width as i64 + x) as usize];
+            let get_neighbour = |sign: i64| {
+                let (neighbour_x, neighbour_y) = (x + x_step * sign, y + y_step * sign);
+                if neighbour_x < 0 || neighbour_y < 0 || neighbour_x >= width as i64 || neighbour_y >= height as i64 {
+                    return 0.0
+                }
+                return magnitudes.get(neighbour_x as u32, neighbour_y as u32)
+            };
+            // >= on one side only, so plateaus two pixels wide keep one pixel
+            if magnitude >= get_neighbour(-1) && magnitude > get_neighbour(1) {
+                thinned.set(x as u32, y as u32, magnitude);
+            }
+        }
+    }
+
+    // Hysteresis, growing out from strong edges through weak ones. Visits are kept apart
+    // from activations, as kept pixels can have 0 activation when low_threshold is 0.
+    let mut activations = activation_map::new(width, height);
+    let mut is_visited: Vec<bool> = vec![false; width as usize * height as usize];
+    let mut to_visit: Vec<(u32, u32)> = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if thinned.get(x, y) >= high_threshold {
+                activations.set(x, y, thinned.get(x, y));
+                is_visited[(y * width + x) as usize] = true;
+                to_visit.push((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = to_visit.pop() {
+        for (neighbour_x, neighbour_y) in get_surrounding_pixels(x, y, width, height) {
+            let index = (neighbour_y * width + neighbour_x) as usize;
+            let magnitude = thinned.get(neighbour_x, neighbour_y);
+            if magnitude >= low_threshold && !is_visited[index] {
+                activations.set(neighbour_x, neighbour_y, magnitude);
+                is_visited[index] = true;
+                to_visit.push((neighbour_x, neighbour_y));
+            }
+        }
+    }
+    return activations
+}
@@ -0,0 +1,366 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use image::{ImageReader, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use crate::bounds::rect;
+use crate::classify::{element_kind, ELEMENT_KINDS};
+use crate::pipeline::detector;
+use crate::synthetic::{ground_truth_element, ground_truth_image};
+
+// Detections and ground truth overlapping by at least this (intersection over union) match
+pub const EVAL_IOU_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug)]
+pub enum dataset_error {
+    Read(String), // A file couldn't be read
+    Invalid(String), // A file was read but isn't a dataset
+}
+
+impl fmt::Display for dataset_error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            dataset_error::Read(message) => write!(f, "couldn't read dataset: {}", message),
+            dataset_error::Invalid(message) => write!(f, "invalid dataset: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for dataset_error {}
+
+// An image and the elements known to be in it
+#[derive(Clone, Debug, PartialEq)]
+pub struct labelled_image {
+    pub path: PathBuf,
+    pub elements: Vec<ground_truth_element>,
+}
+
+// The parts of a COCO object detection file used here
+#[derive(Deserialize)]
+struct coco_image {
+    id: u64,
+    file_name: String,
+}
+
+#[derive(Deserialize)]
+struct coco_annotation {
+    image_id: u64,
+    category_id: u64,
+    bbox: [f32; 4], // x, y, width, height
+}
+
+#[derive(Deserialize)]
+struct coco_category {
+    id: u64,
+    name: String, // An element_kind name, see element_kind::from_name
+}
+
+#[derive(Deserialize)]
+struct coco_dataset {
+    images: Vec<coco_image>,
+    annotations: Vec<coco_annotation>,
+    categories: Vec<coco_category>,
+}
+
+fn parse_coco(value: serde_json::Value, directory: &Path) -> Result<Vec<labelled_image>, dataset_error> {
+    let dataset: coco_dataset = serde_json::from_value(value)
+        .map_err(|error| dataset_error::Invalid(format!("COCO file: {}", error)))?;
+    let mut kinds: Vec<(u64, element_kind)> = Vec::new();
+    for category in &dataset.categories {
+        let kind = element_kind::from_name(&category.name).ok_or_else(|| {
+            dataset_error::Invalid(format!("unknown element kind \"{}\" in COCO categories", category.name))
+        })?;
+        kinds.push((category.id, kind));
+    }
+    let mut images: Vec<labelled_image> = Vec::new();
+    for image in &dataset.images {
+        let mut elements: Vec<ground_truth_element> = Vec::new();
+        for annotation in dataset.annotations.iter().filter(|annotation| annotation.image_id == image.id) {
+            let kind = kinds.iter()
+                .find(|(id, _)| *id == annotation.category_id)
+                .map(|(_, kind)| *kind)
+                .ok_or_else(|| dataset_error::Invalid(format!("unknown COCO category {}", annotation.category_id)))?;
+            let [x, y, width, height] = annotation.bbox;
+            // COCO boxes are in fractional pixels, ones under half a pixel wide still cover one
+            elements.push(ground_truth_element {
+                kind,
+                bounds: rect::new(
+                    x.round() as u32,
+                    y.round() as u32,
+                    (width.round() as u32).max(1),
+                    (height.round() as u32).max(1)
+                ),
+                text: None,
+            });
+        }
+        images.push(labelled_image { path: directory.join(&image.file_name), elements });
+    }
+    return Ok(images)
+}
+
+// Reads a dataset file: either COCO (an object with "images", "annotations" and
+// "categories") or the ground truth written by the synthetic generator, one image or a
+// list of them. Image paths are relative to the file.
+pub fn load_dataset(path: &Path) -> Result<Vec<labelled_image>, dataset_error> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| dataset_error::Read(format!("{}: {}", path.display(), error)))?;
+    let value: serde_json::Value = serde_json::from_str(&contents)
+        .map_err(|error| dataset_error::Invalid(format!("{}: {}", path.display(), error)))?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+    if value.get("annotations").is_some() {
+        return parse_coco(value, directory)
+    }
+    let images: Vec<ground_truth_image> = if value.is_array() {
+        serde_json::from_value(value)
+    } else {
+        serde_json::from_value(value).map(|image| vec![image])
+    }.map_err(|error| dataset_error::Invalid(format!("{}: {}", path.display(), error)))?;
+    return Ok(images.into_iter()
+        .map(|image| labelled_image { path: directory.join(&image.path), elements: image.elements })
+        .collect())
+}
+
+pub fn load_images(dataset: &[labelled_image]) -> Result<Vec<RgbImage>, dataset_error> {
+    let mut images: Vec<RgbImage> = Vec::new();
+    for labelled in dataset {
+        let image = ImageReader::open(&labelled.path)
+            .map_err(|error| dataset_error::Read(format!("{}: {}", labelled.path.display(), error)))?
+            .decode()
+            .map_err(|error| dataset_error::Read(format!("{}: {}", labelled.path.display(), error)))?;
+        images.push(image.into_rgb8());
+    }
+    return Ok(images)
+}
+
+// An element found by the detector, as much as evaluation needs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct detection {
+    pub kind: element_kind,
+    pub bounds: rect,
+    pub confidence: f32,
+}
+
+pub fn get_detections(detector: &detector, buffer: &RgbImage) -> Vec<detection> {
+    return detector.detect(buffer).iter()
+        .map(|element| detection {
+            kind: element.kind,
+            bounds: element.line.bounds,
+            confidence: element.confidence,
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct match_counts {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl match_counts {
+    // Fraction of detections which were right, 0.0 if there were none
+    pub fn precision(&self) -> f32 {
+        let detected = self.true_positives + self.false_positives;
+        return if detected == 0 { 0.0 } else { self.true_positives as f32 / detected as f32 }
+    }
+
+    // Fraction of ground truth which was found, 0.0 if there was none
+    pub fn recall(&self) -> f32 {
+        let labelled = self.true_positives + self.false_negatives;
+        return if labelled == 0 { 0.0 } else { self.true_positives as f32 / labelled as f32 }
+    }
+
+    pub fn f1(&self) -> f32 {
+        let (precision, recall) = (self.precision(), self.recall());
+        return if precision + recall == 0.0 { 0.0 } else { 2.0 * precision * recall / (precision + recall) }
+    }
+
+    fn add(&mut self, other: &match_counts) {
+        self.true_positives += other.true_positives;
+        self.false_positives += other.false_positives;
+        self.false_negatives += other.false_negatives;
+    }
+}
+
+// Results for one element kind over the whole dataset
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct kind_report {
+    pub kind: element_kind,
+    pub counts: match_counts,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+    pub average_precision: Option<f32>, // None if the kind isn't in the ground truth
+}
+
+// Results for one image over every kind
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct image_report {
+    pub path: String,
+    pub counts: match_counts,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct eval_report {
+    pub iou_threshold: f32,
+    pub kinds: Vec<kind_report>, // Only kinds in the ground truth or the detections
+    pub images: Vec<image_report>,
+    pub overall: match_counts,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+    pub mean_average_precision: f32, // Over kinds in the ground truth
+}
+
+// Matches one image's detections of a kind to its ground truth of that kind. Most confident
+// detections choose first, taking the unmatched ground truth they overlap most. Returns
+// whether each detection matched, in the order given.
+fn match_detections(detections: &[&detection], ground_truth: &[&ground_truth_element], iou_threshold: f32) -> Vec<bool> {
+    let mut order: Vec<usize> = (0..detections.len()).collect();
+    order.sort_by(|a, b| detections[*b].confidence.total_cmp(&detections[*a].confidence));
+    let mut is_taken: Vec<bool> = vec![false; ground_truth.len()];
+    let mut is_matched: Vec<bool> = vec![false; detections.len()];
+    for i in order {
+        let best = ground_truth.iter().enumerate()
+            .filter(|(j, _)| !is_taken[*j])
+            .map(|(j, element)| (j, element.bounds.get_iou(&detections[i].bounds)))
+            .filter(|(_, iou)| *iou >= iou_threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((j, _)) = best {
+            is_taken[j] = true;
+            is_matched[i] = true;
+        }
+    }
+    return is_matched
+}
+
+// Area under the precision-recall curve from detections ranked by confidence, with
+// precision at each recall taken as the best at that recall or higher (all-point
+// interpolation, as in Pascal VOC)
+fn get_average_precision(ranked: &mut [(f32, bool)], ground_truth_count: usize) -> f32 {
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut points: Vec<(f32, f32)> = Vec::new(); // (recall, precision)
+    let mut true_positives: usize = 0;
+    for (i, (_, is_match)) in ranked.iter().enumerate() {
+        if *is_match {
+            true_positives += 1;
+        }
+        points.push((true_positives as f32 / ground_truth_count as f32, true_positives as f32 / (i + 1) as f32));
+    }
+    let mut average_precision: f32 = 0.0;
+    let mut previous_recall: f32 = 0.0;
+    for i in 0..points.len() {
+        let (recall, _) = points[i];
+        if recall > previous_recall {
+            let best_precision = points[i..].iter().map(|(_, precision)| *precision).fold(0.0, f32::max);
+            average_precision += (recall - previous_recall) * best_precision;
+            previous_recall = recall;
+        }
+    }
+    return average_precision
+}
+
+// Compares detections (one list per image, in the dataset's order) against the ground truth
+pub fn evaluate(dataset: &[labelled_image], detections: &[Vec<detection>], iou_threshold: f32) -> eval_report {
+    let mut kind_counts: Vec<match_counts> = vec![match_counts::default(); ELEMENT_KINDS.len()];
+    let mut kind_ranked: Vec<Vec<(f32, bool)>> = vec![Vec::new(); ELEMENT_KINDS.len()];
+    let mut images: Vec<image_report> = Vec::new();
+    for (labelled, image_detections) in dataset.iter().zip(detections) {
+        let mut counts = match_counts::default();
+        for (k, kind) in ELEMENT_KINDS.iter().enumerate() {
+            let kind_detections: Vec<&detection> = image_detections.iter().filter(|found| found.kind == *kind).collect();
+            let kind_ground_truth: Vec<&ground_truth_element> = labelled.elements.iter()
+                .filter(|element| element.kind == *kind)
+                .collect();
+            let is_matched = match_detections(&kind_detections, &kind_ground_truth, iou_threshold);
+            let matched_count = is_matched.iter().filter(|is_match| **is_match).count();
+            let kind_image_counts = match_counts {
+                true_positives: matched_count,
+                false_positives: kind_detections.len() - matched_count,
+                false_negatives: kind_ground_truth.len() - matched_count,
+            };
+            counts.add(&kind_image_counts);
+            kind_counts[k].add(&kind_image_counts);
+            kind_ranked[k].extend(kind_detections.iter().zip(is_matched).map(|(found, is_match)| (found.confidence, is_match)));
+        }
+        images.push(image_report {
+            path: labelled.path.display().to_string(),
+            counts,
+            precision: counts.precision(),
+            recall: counts.recall(),
+            f1: counts.f1(),
+        });
+    }
+
+    let mut kinds: Vec<kind_report> = Vec::new();
+    let mut overall = match_counts::default();
+    for (k, kind) in ELEMENT_KINDS.iter().enumerate() {
+        let counts = kind_counts[k];
+        overall.add(&counts);
+        let ground_truth_count = counts.true_positives + counts.false_negatives;
+        if ground_truth_count == 0 && counts.false_positives == 0 {
+            continue
+        }
+        kinds.push(kind_report {
+            kind: *kind,
+            counts,
+            precision: counts.precision(),
+            recall: counts.recall(),
+            f1: counts.f1(),
+            average_precision: (ground_truth_count > 0)
+                .then(|| get_average_precision(&mut kind_ranked[k], ground_truth_count)),
+        });
+    }
+    let average_precisions: Vec<f32> = kinds.iter().filter_map(|kind| kind.average_precision).collect();
+    let mean_average_precision = if average_precisions.is_empty() {
+        0.0
+    } else {
+        average_precisions.iter().sum::<f32>() / average_precisions.len() as f32
+    };
+    return eval_report {
+        iou_threshold,
+        kinds,
+        images,
+        overall,
+        precision: overall.precision(),
+        recall: overall.recall(),
+        f1: overall.f1(),
+        mean_average_precision,
+    }
+}
+
+// Report as aligned tables, per kind then per image
+pub fn format_report(report: &eval_report) -> String {
+    let image_names: Vec<String> = report.images.iter()
+        .map(|image| Path::new(&image.path).file_name().map_or(image.path.clone(), |name| name.to_string_lossy().to_string()))
+        .collect();
+    let name_width = image_names.iter().map(|name| name.len()).chain([14]).max().unwrap_or(14);
+    let get_row = |name: &str, counts: &match_counts| format!(
+        "{:<width$} {:>6} {:>6} {:>6} {:>9.3} {:>7.3} {:>6.3}",
+        name, counts.true_positives, counts.false_positives, counts.false_negatives,
+        counts.precision(), counts.recall(), counts.f1(), width = name_width
+    );
+    let header = format!(
+        "{:<width$} {:>6} {:>6} {:>6} {:>9} {:>7} {:>6}",
+        "", "tp", "fp", "fn", "precision", "recall", "f1", width = name_width
+    );
+
+    let mut lines: Vec<String> = vec![format!("IoU threshold {}", report.iou_threshold)];
+    lines.push(format!("{} {:>6}", header, "ap"));
+    for kind in &report.kinds {
+        let average_precision = kind.average_precision.map_or("-".to_string(), |ap| format!("{:.3}", ap));
+        lines.push(format!("{} {:>6}", get_row(kind.kind.get_name(), &kind.counts), average_precision));
+    }
+    lines.push(get_row("overall", &report.overall));
+    lines.push(format!("mAP {:.3}", report.mean_average_precision));
+    lines.push(String::new());
+    lines.push(header);
+    for (name, image) in image_names.iter().zip(&report.images) {
+        lines.push(get_row(name, &image.counts));
+    }
+    return lines.join("\n")
+}
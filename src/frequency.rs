@@ -0,0 +1,302 @@
+use image::{ImageBuffer, Pixel};
+use num_complex::Complex32;
+use serde::{Deserialize, Serialize};
+
+use crate::bounds::rect;
+use crate::pipeline::element;
+
+// In place fast Fourier transform (iterative radix-2 Cooley-Tukey).
+// values.len() must be a power of two. The inverse is scaled by 1 / len.
+pub fn fft(values: &mut [Complex32], inverse: bool) {
+    let len = values.len();
+    assert!(len.is_power_of_two(), "fft length must be a power of two, got {}", len);
+    // Bit reversal permutation
+    let mut j: usize = 0;
+    for i in 1..len {
+        let mut bit = len >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let sign: f32 = if inverse { 1.0 } else { -1.0 };
+    let mut size: usize = 2;
+    while size <= len {
+        let step = Complex32::from_polar(1.0, sign * 2.0 * std::f32::consts::PI / size as f32);
+        for start in (0..len).step_by(size) {
+            let mut twiddle = Complex32::new(1.0, 0.0);
+            for k in 0..size / 2 {
+                let even = values[start + k];
+                let odd = values[start + k + size / 2] * twiddle;
+                values[start + k] = even + odd;
+                values[start + k + size / 2] = even - odd;
+                twiddle *= step;
+            }
+        }
+        size *= 2;
+    }
+    if inverse {
+        values.iter_mut().for_each(|value| *value /= len as f32);
+    }
+}
+
+// 2D spectrum of a region, padded with zeros up to powers of two
+pub struct spectrum {
+    width: usize,
+    height: usize,
+    values: Vec<Complex32>, // Row major, (0, 0) is the mean
+}
+
+impl spectrum {
+    pub fn width(&self) -> usize {
+        return self.width
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height
+    }
+
+    // Coefficient for x_frequency / width cycles per pixel across and y_frequency / height down
+    pub fn get(&self, x_frequency: usize, y_frequency: usize) -> Complex32 {
+        return self.values[y_frequency * self.width + x_frequency]
+    }
+
+    pub fn get_power(&self, x_frequency: usize, y_frequency: usize) -> f32 {
+        return self.get(x_frequency, y_frequency).norm_sqr()
+    }
+}
+
+// 2D FFT of row major values, rows then columns
+pub fn get_fft_2d(values: &[f32], width: usize, height: usize) -> spectrum {
+    let (padded_width, padded_height) = (width.next_power_of_two(), height.next_power_of_two());
+    let mut padded: Vec<Complex32> = vec![Complex32::new(0.0, 0.0); padded_width * padded_height];
+    for y in 0..height {
+        for x in 0..width {
+            padded[y * padded_width + x] = Complex32::new(values[y * width + x], 0.0);
+        }
+    }
+    for row in padded.chunks_mut(padded_width) {
+        fft(row, false);
+    }
+    let mut column: Vec<Complex32> = vec![Complex32::new(0.0, 0.0); padded_height];
+    for x in 0..padded_width {
+        for y in 0..padded_height {
+            column[y] = padded[y * padded_width + x];
+        }
+        fft(&mut column, false);
+        for y in 0..padded_height {
+            padded[y * padded_width + x] = column[y];
+        }
+    }
+    return spectrum {
+        width: padded_width,
+        height: padded_height,
+        values: padded,
+    }
+}
+
+// The spectrum of the region's intensity (mean of the channels) with the mean taken away, so
+// the padding isn't an edge and power is all from changes in the region
+pub fn get_region_spectrum(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>, region: &rect) -> spectrum {
+    let mut intensities: Vec<f32> = Vec::with_capacity(region.area() as usize);
+    for y in region.y..=region.bottom() {
+        for x in region.x..=region.right() {
+            let channels = buffer.get_pixel(x, y).channels();
+            intensities.push(channels.iter().map(|channel| *channel as f32).sum::<f32>() / channels.len() as f32);
+        }
+    }
+    let mean = intensities.iter().sum::<f32>() / intensities.len() as f32;
+    intensities.iter_mut().for_each(|intensity| *intensity -= mean);
+    return get_fft_2d(&intensities, region.width as usize, region.height as usize)
+}
+
+// Frequencies above this (in cycles per pixel, at most 0.5) repeat every few pixels, like
+// the strokes of text. Photos and gradients have little power up here.
+pub const HIGH_FREQUENCY_CUTOFF: f32 = 0.2;
+
+// Distance of frequency from 0 in cycles per pixel, as frequencies past len / 2 are negative
+fn get_signed_frequency(frequency: usize, len: usize) -> f32 {
+    return frequency.min(len - frequency) as f32 / len as f32
+}
+
+// Fraction of the spectrum's power (ignoring the mean) above HIGH_FREQUENCY_CUTOFF
+pub fn get_high_frequency_share(spectrum: &spectrum) -> f32 {
+    let mut total: f32 = 0.0;
+    let mut high: f32 = 0.0;
+    for y_frequency in 0..spectrum.height() {
+        let y = get_signed_frequency(y_frequency, spectrum.height());
+        for x_frequency in 0..spectrum.width() {
+            if x_frequency == 0 && y_frequency == 0 {
+                continue
+            }
+            let x = get_signed_frequency(x_frequency, spectrum.width());
+            let power = spectrum.get_power(x_frequency, y_frequency);
+            total += power;
+            if x.hypot(y) >= HIGH_FREQUENCY_CUTOFF {
+                high += power;
+            }
+        }
+    }
+    if total == 0.0 {
+        return 0.0
+    }
+    return high / total
+}
+
+// Something repeating along one axis
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct periodicity {
+    pub period: f32, // Pixels between repeats
+    pub strength: f32, // Correlation of the region with itself shifted by period, 0.0 - 1.0
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum axis {
+    X, // Columns repeating across
+    Y, // Rows repeating down
+}
+
+// Mean intensity of each column (X) or row (Y) of the region, less the overall mean.
+// Its spectrum is the 2D spectrum's row or column through 0 (projection-slice theorem).
+pub fn get_profile(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>, region: &rect, axis: axis) -> Vec<f32> {
+    let (len, across) = match axis {
+        axis::X => (region.width, region.height),
+        axis::Y => (region.height, region.width),
+    };
+    let mut profile: Vec<f32> = vec![0.0; len as usize];
+    for i in 0..len {
+        for j in 0..across {
+            let (x, y) = match axis {
+                axis::X => (region.x + i, region.y + j),
+                axis::Y => (region.x + j, region.y + i),
+            };
+            let channels = buffer.get_pixel(x, y).channels();
+            profile[i as usize] += channels.iter().map(|channel| *channel as f32).sum::<f32>() / channels.len() as f32;
+        }
+        profile[i as usize] /= across as f32;
+    }
+    let mean = profile.iter().sum::<f32>() / profile.len() as f32;
+    profile.iter_mut().for_each(|value| *value -= mean);
+    return profile
+}
+
+// Correlation of values with itself shifted by each lag, 1.0 at lag 0. Found through the
+// power spectrum (Wiener-Khinchin), padded to twice the length so shifts don't wrap around.
+// Each lag is scaled up by how much of values still overlaps, so long lags aren't penalised.
+pub fn get_autocorrelation(values: &[f32]) -> Vec<f32> {
+    let padded_len = (values.len() * 2).next_power_of_two();
+    let mut padded: Vec<Complex32> = vec![Complex32::new(0.0, 0.0); padded_len];
+    for (i, value) in values.iter().enumerate() {
+        padded[i] = Complex32::new(*value, 0.0);
+    }
+    fft(&mut padded, false);
+    padded.iter_mut().for_each(|value| *value = Complex32::new(value.norm_sqr(), 0.0));
+    fft(&mut padded, true);
+    let zero_lag = padded[0].re;
+    if zero_lag <= 0.0 {
+        return vec![0.0; values.len()]
+    }
+    return (0..values.len())
+        .map(|lag| padded[lag].re / zero_lag * values.len() as f32 / (values.len() - lag) as f32)
+        .collect()
+}
+
+// Shortest repeat counted, shorter ones are text strokes or textures rather than layout
+pub const MIN_PERIOD: usize = 16;
+// A repeat must fit in the region at least this many times
+pub const MIN_REPEATS: usize = 3;
+// Shorter periods within this fraction of the best correlation are preferred, as anything
+// repeating every p pixels also repeats every 2p
+const PERIOD_TOLERANCE: f32 = 0.9;
+
+// Strongest repeat in a profile (see get_profile), None if there is no peak between
+// MIN_PERIOD and a MIN_REPEATS'th of its length
+pub fn get_periodicity(profile: &[f32]) -> Option<periodicity> {
+    let max_period = profile.len() / MIN_REPEATS;
+    if max_period <= MIN_PERIOD {
+        return None
+    }
+    let autocorrelation = get_autocorrelation(profile);
+    // Local peaks only, the slope down from lag 0 isn't a repeat
+    let peaks: Vec<usize> = (MIN_PERIOD..=max_period)
+        .filter(|lag| {
+            autocorrelation[*lag] > 0.0
+                && autocorrelation[*lag] >= autocorrelation[lag - 1]
+                && autocorrelation[*lag] >= autocorrelation[lag + 1]
+        })
+        .collect();
+    let best = peaks.iter().map(|lag| autocorrelation[*lag]).fold(0.0, f32::max);
+    let period = peaks.into_iter().find(|lag| autocorrelation[*lag] >= PERIOD_TOLERANCE * best)?;
+    return Some(periodicity {
+        period: period as f32,
+        strength: autocorrelation[period].min(1.0),
+    })
+}
+
+// Minimum strength for a repeat to be a layout rather than chance
+pub const MIN_LAYOUT_STRENGTH: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum layout_kind {
+    Grid, // Repeats both ways
+    List, // Rows repeating down
+    Row, // Columns repeating across, e.g. a toolbar or tab bar
+}
+
+// A region found to be made of repeated cells
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct layout {
+    pub kind: layout_kind,
+    pub bounds: rect,
+    pub column_width: Option<f32>,
+    pub row_height: Option<f32>,
+}
+
+// Grid and list detector: looks for repeats in the region strong enough to be a layout of
+// cells, e.g. the rows of a settings list or the tiles of a photo grid
+pub fn detect_layout(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>, region: &rect) -> Option<layout> {
+    // Only the 1D profiles are needed, not the region's full spectrum
+    let x_periodicity = get_periodicity(&get_profile(buffer, region, axis::X));
+    let y_periodicity = get_periodicity(&get_profile(buffer, region, axis::Y));
+    let is_strong = |periodicity: Option<periodicity>| {
+        periodicity.filter(|periodicity| periodicity.strength >= MIN_LAYOUT_STRENGTH)
+    };
+    let columns = is_strong(x_periodicity);
+    let rows = is_strong(y_periodicity);
+    let kind = match (columns, rows) {
+        (Some(_), Some(_)) => layout_kind::Grid,
+        (None, Some(_)) => layout_kind::List,
+        (Some(_), None) => layout_kind::Row,
+        (None, None) => return None,
+    };
+    return Some(layout {
+        kind,
+        bounds: *region,
+        column_width: columns.map(|periodicity| periodicity.period),
+        row_height: rows.map(|periodicity| periodicity.period),
+    })
+}
+
+// Elements smaller than this either way can't hold MIN_REPEATS cells worth finding
+const MIN_LAYOUT_SIZE: u32 = 3 * MIN_PERIOD as u32;
+
+// detect_layout over the whole image and every element containing others
+pub fn get_layouts(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>, elements: &[element]) -> Vec<layout> {
+    if buffer.width() == 0 || buffer.height() == 0 {
+        return Vec::new()
+    }
+    let mut regions: Vec<rect> = vec![rect::new(0, 0, buffer.width(), buffer.height())];
+    for element in elements {
+        let bounds = element.line.bounds;
+        if !element.children.is_empty() && bounds.width >= MIN_LAYOUT_SIZE && bounds.height >= MIN_LAYOUT_SIZE {
+            regions.push(bounds);
+        }
+    }
+    return regions.iter().filter_map(|region| detect_layout(buffer, region)).collect()
+}
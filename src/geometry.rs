@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+// Rectangle at any angle, for slanted UI (photos of screens, rotated labels) which an
+// axis aligned rect fits loosely. Coordinates are continuous: pixel (x, y) covers
+// x..x+1, y..y+1, so an unrotated box matches the line's rect exactly.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct rotated_rect {
+    pub center: (f32, f32),
+    pub width: f32, // Along the angle
+    pub height: f32,
+    pub angle: f32, // Radians clockwise from the x axis (y points down), -pi/4 < angle <= pi/4
+}
+
+impl rotated_rect {
+    pub fn area(&self) -> f32 {
+        return self.width * self.height
+    }
+
+    // Corners in order around the box, starting top left when unrotated
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let (sin, cos) = self.angle.sin_cos();
+        let along = (cos * self.width / 2.0, sin * self.width / 2.0);
+        let across = (-sin * self.height / 2.0, cos * self.height / 2.0);
+        let (cx, cy) = self.center;
+        return [
+            (cx - along.0 - across.0, cy - along.1 - across.1),
+            (cx + along.0 - across.0, cy + along.1 - across.1),
+            (cx + along.0 + across.0, cy + along.1 + across.1),
+            (cx - along.0 + across.0, cy - along.1 + across.1),
+        ]
+    }
+}
+
+// z component of (a - origin) x (b - origin), positive when origin -> a -> b turns clockwise
+// on screen (anticlockwise with y up)
+fn cross(origin: (i64, i64), a: (i64, i64), b: (i64, i64)) -> i64 {
+    return (a.0 - origin.0) * (b.1 - origin.1) - (a.1 - origin.1) * (b.0 - origin.0)
+}
+
+// Corners of the pixels which can be on the hull. Only the leftmost and rightmost pixel of
+// each row can be, so the rest are skipped.
+fn get_outline_corners(pixels: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut rows: std::collections::BTreeMap<u32, (u32, u32)> = std::collections::BTreeMap::new();
+    for (x, y) in pixels {
+        let row = rows.entry(*y).or_insert((*x, *x));
+        row.0 = row.0.min(*x);
+        row.1 = row.1.max(*x);
+    }
+    let mut corners: Vec<(u32, u32)> = Vec::new();
+    for (y, (min_x, max_x)) in rows {
+        corners.extend([(min_x, y), (min_x, y + 1), (max_x + 1, y), (max_x + 1, y + 1)]);
+    }
+    return corners
+}
+
+// Convex hull of the pixels' corners (Andrew's monotone chain), in order with no
+// repeated or collinear points. Empty if there are no pixels.
+pub fn get_convex_hull(pixels: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut points = get_outline_corners(pixels);
+    points.sort();
+    points.dedup();
+    if points.len() < 3 {
+        return points
+    }
+    let mut hull: Vec<(u32, u32)> = Vec::new();
+    let as_i64 = |point: (u32, u32)| (point.0 as i64, point.1 as i64);
+    // Lower half then upper half, each point popping any that would make a clockwise turn
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &(u32, u32)>> = if pass == 0 {
+            Box::new(points.iter())
+        } else {
+            Box::new(points.iter().rev())
+        };
+        for point in ordered {
+            while hull.len() >= start + 2
+                && cross(as_i64(hull[hull.len() - 2]), as_i64(hull[hull.len() - 1]), as_i64(*point)) <= 0 {
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+        hull.pop(); // Start of the other half
+    }
+    return hull
+}
+
+// Smallest rectangle containing the hull, found with rotating calipers: the best rectangle
+// has a side along one of the hull's edges, so each edge is tried in turn while the
+// extreme points in the other three directions are walked round with it.
+pub fn get_min_area_rect(hull: &[(u32, u32)]) -> Option<rotated_rect> {
+    if hull.len() < 3 {
+        return None
+    }
+    let points: Vec<(f64, f64)> = hull.iter().map(|point| (point.0 as f64, point.1 as f64)).collect();
+    let count = points.len();
+    let dot = |a: (f64, f64), b: (f64, f64)| a.0 * b.0 + a.1 * b.1;
+
+    let mut best: Option<(f64, rotated_rect)> = None;
+    // Indices of the furthest point along the edge, across it, and back along it
+    let (mut far, mut across, mut back) = (1, 1, 1);
+    for i in 0..count {
+        let start = points[i];
+        let end = points[(i + 1) % count];
+        let length = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt();
+        let direction = ((end.0 - start.0) / length, (end.1 - start.1) / length);
+        let normal = (-direction.1, direction.0);
+        let relative = |index: usize| (points[index % count].0 - start.0, points[index % count].1 - start.1);
+
+        // Each extreme only moves forwards round the hull as the edge does
+        while dot(relative(far + 1), direction) > dot(relative(far), direction) {
+            far += 1;
+        }
+        across = across.max(far);
+        while dot(relative(across + 1), normal).abs() > dot(relative(across), normal).abs() {
+            across += 1;
+        }
+        back = back.max(across);
+        while dot(relative(back + 1), direction) < dot(relative(back), direction) {
+            back += 1;
+        }
+
+        let max_along = dot(relative(far), direction);
+        let min_along = dot(relative(back), direction).min(0.0);
+        let height = dot(relative(across), normal).abs();
+        let width = max_along - min_along;
+        let area = width * height;
+        if best.as_ref().is_some_and(|(best_area, _)| *best_area <= area) {
+            continue
+        }
+        // Normal points away from the hull, so the box lies on the other side of the edge
+        let side = if dot(relative(across), normal) < 0.0 { -1.0 } else { 1.0 };
+        let middle_along = (max_along + min_along) / 2.0;
+        let center = (
+            start.0 + direction.0 * middle_along + normal.0 * side * height / 2.0,
+            start.1 + direction.1 * middle_along + normal.1 * side * height / 2.0,
+        );
+        best = Some((area, get_normalised_rect(center, width, height, direction.1.atan2(direction.0))));
+    }
+    return best.map(|(_, rect)| rect)
+}
+
+// Turns the rect by quarter turns until -pi/4 < angle <= pi/4, so equal boxes compare equal
+fn get_normalised_rect(center: (f64, f64), mut width: f64, mut height: f64, mut angle: f64) -> rotated_rect {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+    while angle > FRAC_PI_4 + 1e-9 {
+        angle -= FRAC_PI_2;
+        std::mem::swap(&mut width, &mut height);
+    }
+    while angle <= -FRAC_PI_4 + 1e-9 {
+        angle += FRAC_PI_2;
+        std::mem::swap(&mut width, &mut height);
+    }
+    return rotated_rect {
+        center: (center.0 as f32, center.1 as f32),
+        width: width as f32,
+        height: height as f32,
+        angle: angle as f32,
+    }
+}
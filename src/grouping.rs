@@ -0,0 +1,163 @@
+use crate::classify::element_kind;
+use crate::color::{color_difference, get_color_difference};
+use crate::lines::{find_group, get_lines_stats, line};
+use crate::pipeline::{element, element_text};
+
+// Clusters on the same line of text, relative to their heights
+const MIN_VERTICAL_OVERLAP: f32 = 0.5; // Of the shorter cluster
+const MAX_HEIGHT_RATIO: f32 = 2.5; // Allows e.g. 'o' next to 'l'
+const MAX_BASELINE_DIFFERENCE: f32 = 0.5; // Allows for descenders
+const MAX_WORD_GAP: f32 = 1.0; // Wider gaps separate phrases, e.g. columns
+// Lines in the same paragraph, relative to line height
+const MAX_LINE_HEIGHT_RATIO: f32 = 1.5;
+const MAX_LINE_GAP: f32 = 0.8;
+const MAX_INDENT: f32 = 2.0; // Difference between left edges
+
+// Groups 0..count so any two items which should_join are in the same group (transitively).
+// Groups are ordered by their first item.
+fn get_groups(count: usize, should_join: impl Fn(usize, usize) -> bool) -> Vec<Vec<usize>> {
+    let mut group_parents: Vec<usize> = (0..count).collect();
+    for i in 0..count {
+        for j in (i + 1)..count {
+            if should_join(i, j) {
+                let root_i = find_group(&mut group_parents, i);
+                let root_j = find_group(&mut group_parents, j);
+                group_parents[root_j] = root_i;
+            }
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_indices: Vec<Option<usize>> = vec![None; count];
+    for i in 0..count {
+        let root = find_group(&mut group_parents, i);
+        match group_indices[root] {
+            Some(group_index) => groups[group_index].push(i),
+            None => {
+                group_indices[root] = Some(groups.len());
+                groups.push(vec![i]);
+            }
+        }
+    }
+    return groups;
+}
+
+// True if two text clusters read as neighbouring letters or words
+fn is_same_text_line(a: &line, b: &line) -> bool {
+    let (a_x0, a_y0, a_x1, a_y1) = (a.bounds.x, a.bounds.y, a.bounds.right(), a.bounds.bottom());
+    let (b_x0, b_y0, b_x1, b_y1) = (b.bounds.x, b.bounds.y, b.bounds.right(), b.bounds.bottom());
+    let a_height = a.bounds.height as f32;
+    let b_height = b.bounds.height as f32;
+    let shorter = a_height.min(b_height);
+    let taller = a_height.max(b_height);
+
+    let vertical_overlap = std::cmp::min(a_y1, b_y1) as f32 - std::cmp::max(a_y0, b_y0) as f32 + 1.0;
+    let baseline_difference = (a_y1 as f32 - b_y1 as f32).abs();
+    // Negative when the boxes overlap horizontally
+    let gap = std::cmp::max(b_x0 as i64 - a_x1 as i64, a_x0 as i64 - b_x1 as i64) - 1;
+    return vertical_overlap >= MIN_VERTICAL_OVERLAP * shorter
+        && taller / shorter <= MAX_HEIGHT_RATIO
+        && baseline_difference <= MAX_BASELINE_DIFFERENCE * taller
+        && gap as f32 <= MAX_WORD_GAP * taller;
+}
+
+fn merge_lines(lines: Vec<line>) -> line {
+    let pixels: Vec<(u32, u32)> = lines.into_iter().flat_map(|line| line.pixels).collect();
+    return get_lines_stats(vec![pixels]).remove(0);
+}
+
+// Merges text clusters (letters, words) on the same line of text and with the same
+// stroke colour (no more than color_threshold apart by color_difference) into one line per phrase.
+// stroke_colors is per line, None for non-text, and is returned matching the new lines.
+pub fn merge_text_lines(
+    lines: Vec<line>,
+    stroke_colors: Vec<Option<image::Rgb<u8>>>,
+    color_difference: color_difference,
+    color_threshold: f32
+) -> (Vec<line>, Vec<Option<image::Rgb<u8>>>) {
+    let groups = get_groups(lines.len(), |i, j| {
+        match (stroke_colors[i], stroke_colors[j]) {
+            (Some(i_color), Some(j_color)) => get_color_difference(&i_color, &j_color, color_difference) <= color_threshold
+                && is_same_text_line(&lines[i], &lines[j]),
+            _ => false,
+        }
+    });
+
+    let mut lines: Vec<Option<line>> = lines.into_iter().map(Some).collect();
+    let mut merged_lines: Vec<line> = Vec::new();
+    let mut merged_stroke_colors: Vec<Option<image::Rgb<u8>>> = Vec::new();
+    for group in groups {
+        let group_lines: Vec<line> = group.iter().filter_map(|i| lines[*i].take()).collect();
+        merged_stroke_colors.push(stroke_colors[group[0]]);
+        if group_lines.len() == 1 {
+            merged_lines.extend(group_lines);
+        } else {
+            merged_lines.push(merge_lines(group_lines));
+        }
+    }
+    return (merged_lines, merged_stroke_colors);
+}
+
+// True if two lines of text read as consecutive lines of a paragraph
+fn is_same_paragraph(a: &element, b: &element, color_difference: color_difference, color_threshold: f32) -> bool {
+    let (Some(a_text), Some(b_text)) = (&a.text, &b.text) else {
+        return false
+    };
+    let (upper, lower) = if a.line.bounds.y <= b.line.bounds.y {
+        (a.line.bounds, b.line.bounds)
+    } else {
+        (b.line.bounds, a.line.bounds)
+    };
+    let upper_height = upper.height as f32;
+    let lower_height = lower.height as f32;
+    let taller = upper_height.max(lower_height);
+
+    let gap = lower.y as i64 - upper.bottom() as i64 - 1;
+    let indent = (upper.x as f32 - lower.x as f32).abs();
+    return get_color_difference(&a_text.stroke_color, &b_text.stroke_color, color_difference) <= color_threshold
+        && taller / upper_height.min(lower_height) <= MAX_LINE_HEIGHT_RATIO
+        && gap >= 0
+        && gap as f32 <= MAX_LINE_GAP * taller
+        && indent <= MAX_INDENT * taller;
+}
+
+// Adds a text label element for every group of two or more lines of text forming a
+// paragraph. The lines are left in place, so become the paragraph's children.
+// Lines' stroke colours must be no more than color_threshold apart by color_difference.
+pub fn add_paragraphs(elements: &mut Vec<element>, color_difference: color_difference, color_threshold: f32) {
+    let groups = get_groups(elements.len(), |i, j| {
+        is_same_paragraph(&elements[i], &elements[j], color_difference, color_threshold)
+    });
+    for mut group in groups {
+        if group.len() < 2 {
+            continue
+        }
+        group.sort_by_key(|i| elements[*i].line.bounds.y);
+        let first = &elements[group[0]];
+        let stroke_color = first.text.as_ref().map_or(first.dominant_color, |text| text.stroke_color);
+        let dominant_color = first.dominant_color;
+
+        let mut texts: Vec<String> = Vec::new();
+        let mut words = Vec::new();
+        let mut pixels: Vec<(u32, u32)> = Vec::new();
+        let mut confidence: f32 = 1.0;
+        for i in &group {
+            let element = &elements[*i];
+            if let Some(text) = &element.text {
+                texts.push(text.text.clone());
+                words.extend(text.words.iter().cloned());
+            }
+            pixels.extend(element.line.pixels.iter().cloned());
+            confidence = confidence.min(element.confidence);
+        }
+        elements.push(element {
+            line: get_lines_stats(vec![pixels]).remove(0),
+            dominant_color,
+            text: Some(element_text { stroke_color, text: texts.join(" "), words }),
+            kind: element_kind::TextLabel,
+            confidence,
+            parent: None,
+            children: Vec::new(),
+            label: None,
+        });
+    }
+}
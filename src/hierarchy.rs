@@ -0,0 +1,76 @@
+use crate::classify::is_inside;
+use crate::element;
+
+// Links every element to the smallest element containing it, so e.g. a button's
+// outline becomes the parent of its text, and a dialog the parent of its buttons.
+// Parent and children are indices into elements.
+pub fn build_hierarchy(elements: &mut [element]) {
+    let mut parents: Vec<Option<usize>> = Vec::new();
+    for element in elements.iter() {
+        let mut parent: Option<usize> = None;
+        let mut parent_area = u32::MAX;
+        for (j, other) in elements.iter().enumerate() {
+            if is_inside(&element.line, &other.line) && other.line.bounds.area() < parent_area {
+                parent = Some(j);
+                parent_area = other.line.bounds.area();
+            }
+        }
+        parents.push(parent);
+    }
+    for element in elements.iter_mut() {
+        element.children.clear();
+    }
+    for (i, parent) in parents.iter().enumerate() {
+        elements[i].parent = *parent;
+        if let Some(parent) = parent {
+            elements[*parent].children.push(i);
+        }
+    }
+    for i in 0..elements.len() {
+        let mut children = std::mem::take(&mut elements[i].children);
+        // Reading order: top to bottom, then left to right
+        children.sort_by_key(|child| {
+            let bounds = elements[*child].line.bounds;
+            (bounds.y, bounds.x)
+        });
+        let texts: Vec<&str> = children.iter()
+            .filter_map(|child| elements[*child].text.as_ref())
+            .map(|text| text.text.as_str())
+            .filter(|text| !text.is_empty())
+            .collect();
+        elements[i].label = if texts.is_empty() { None } else { Some(texts.join(" ")) };
+        elements[i].children = children;
+    }
+}
+
+// Indices of the element's parent, its parent's parent... up to the outermost element
+pub fn get_ancestors(elements: &[element], index: usize) -> Vec<usize> {
+    let mut ancestors: Vec<usize> = Vec::new();
+    let mut current = elements[index].parent;
+    while let Some(parent) = current {
+        ancestors.push(parent);
+        current = elements[parent].parent;
+    }
+    return ancestors;
+}
+
+// Describes an element by what it is and where it is, e.g.
+// `button "OK" inside container "Save"`, for referring to it by voice
+pub fn describe_element(elements: &[element], index: usize) -> String {
+    let describe_one = |element: &element| {
+        let name = element.label.as_deref()
+            .or(element.text.as_ref().map(|text| text.text.as_str()))
+            .filter(|name| !name.is_empty());
+        let kind = element.kind.get_name();
+        return match name {
+            Some(name) => format!("{} \"{}\"", kind, name),
+            None => kind.to_string(),
+        }
+    };
+    let mut description = describe_one(&elements[index]);
+    for ancestor in get_ancestors(elements, index) {
+        description += " inside ";
+        description += &describe_one(&elements[ancestor]);
+    }
+    return description;
+}
@@ -0,0 +1,36 @@
+// Finds UI elements (buttons, text, boxes...) in screenshots by looking for "lines" -
+// clusters of pixels which differ from their surroundings.
+// Pipeline: difference_filter -> get_lines -> get_lines_stats -> sanitise_lines
+// -> suppress_duplicates (optional) -> get_text_lines
+// -> merge_text_lines -> transcribe_text_lines -> classify -> add_paragraphs -> build_hierarchy
+// A detector runs the whole pipeline, optionally up to sanitise_lines over an image pyramid
+// (see get_pyramid). The individual stages are public for finer control.
+// Its detector_config holds every setting, loadable from TOML or JSON and starting from a
+// config_profile.
+
+pub mod activation;
+pub mod bounds;
+pub mod classify;
+pub mod color;
+pub mod draw;
+pub mod edges;
+pub mod eval;
+pub mod frequency;
+pub mod geometry;
+pub mod grouping;
+pub mod hierarchy;
+pub mod lines;
+pub mod nms;
+pub mod ocr;
+pub mod output;
+pub mod pipeline;
+pub mod profile;
+pub mod pyramid;
+pub mod synthetic;
+pub mod text;
+pub mod threshold;
+pub mod tune;
+
+pub use bounds::rect;
+pub use classify::element_kind;
+pub use pipeline::{detector, detector_config, element};
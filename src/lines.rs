@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+
+use crate::activation::activation_map;
+use crate::bounds::rect;
+use crate::geometry::{get_convex_hull, get_min_area_rect, rotated_rect};
+
+pub fn get_surrounding_pixels(x: u32, y: u32, width: u32, height: u32) -> Vec<(u32, u32)>{
+    let mut pixels: Vec<(u32, u32)> = Vec::new();
+    for x_offs in -1..=1 {
+        for y_offs in -1..=1 {
+            if x_offs == 0 && y_offs == 0 {
+                continue
+            }
+            let offs_x = x as i32 + x_offs;
+            let offs_y = y as i32 + y_offs;
+
+            if offs_x < 0
+                || offs_x >= width as i32
+                || offs_y < 0
+                || offs_y>= height as i32 {
+                continue
+            }
+            pixels.push((offs_x as u32, offs_y as u32));
+        }
+    }
+    return pixels;
+}
+
+// Which neighbours count as touching when pixels are grouped into lines
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum connectivity {
+    Four, // Left, right, up and down
+    Eight, // Diagonals too, see get_surrounding_pixels
+}
+
+impl connectivity {
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            connectivity::Four => "four",
+            connectivity::Eight => "eight",
+        }
+    }
+
+    // Also accepts "4" and "8"
+    pub fn from_name(name: &str) -> Option<connectivity> {
+        return match name.trim().to_lowercase().as_str() {
+            "four" | "4" => Some(connectivity::Four),
+            "eight" | "8" => Some(connectivity::Eight),
+            _ => None,
+        }
+    }
+
+    // Neighbours already passed when scanning column by column, top to bottom
+    fn get_previous_offsets(&self) -> &'static [(i64, i64)] {
+        return match self {
+            connectivity::Four => &[(-1, 0), (0, -1)],
+            connectivity::Eight => &[(-1, -1), (-1, 0), (-1, 1), (0, -1)],
+        }
+    }
+}
+
+impl std::fmt::Display for connectivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.get_name())
+    }
+}
+
+impl std::str::FromStr for connectivity {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<connectivity, String> {
+        return connectivity::from_name(name)
+            .ok_or_else(|| format!("unknown connectivity \"{}\", expected four (4) or eight (8)", name))
+    }
+}
+
+// Root of i's group in a union-find forest
+pub fn find_group(group_parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while group_parents[root] != root {
+        root = group_parents[root];
+    }
+    // Points everything on the way straight at the root, so later finds are quick
+    let mut current = i;
+    while group_parents[current] != root {
+        let next = group_parents[current];
+        group_parents[current] = root;
+        current = next;
+    }
+    return root;
+}
+
+// Which line (if any) every pixel is part of, see label_components
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct label_image {
+    width: u32,
+    height: u32,
+    labels: Vec<u32>, // Row major, 0 for no line, otherwise index + 1
+}
+
+impl label_image {
+    pub fn width(&self) -> u32 {
+        return self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        return self.height
+    }
+
+    // Index of the line the pixel is part of
+    pub fn get(&self, x: u32, y: u32) -> Option<usize> {
+        let label = self.labels[y as usize * self.width as usize + x as usize];
+        return label.checked_sub(1).map(|index| index as usize)
+    }
+}
+
+// Lines with no more pixels than this are dropped
+// Likely just artefacts -> invisible, not pressable buttons
+const MIN_LINE_PIXELS: usize = 4;
+
+// Groups activated pixels into lines (clusters of touching pixels) with two-pass union-find
+// labelling, leaving the activation map untouched. Pixels above the grow threshold are
+// joined to touching ones, then only groups containing a pixel above the seed threshold
+// are kept (see get_hysteresis_lines). Returns which line each pixel is part of, and the
+// pixels of each line. Lines are ordered by their first pixel going column by column.
+pub fn label_components(
+    activation_map: &activation_map,
+    get_seed_threshold: impl Fn(u32, u32) -> f32,
+    get_grow_threshold: impl Fn(u32, u32) -> f32,
+    connectivity: connectivity
+) -> (label_image, Vec<Vec<(u32, u32)>>) {
+    let (width, height) = (activation_map.width(), activation_map.height());
+    let get_index = |x: u32, y: u32| y as usize * width as usize + x as usize;
+    // Provisional label of each pixel, usize::MAX for unactivated ones
+    let mut provisional: Vec<usize> = vec![usize::MAX; width as usize * height as usize];
+    let mut label_parents: Vec<usize> = Vec::new();
+    let mut is_seeded: Vec<bool> = Vec::new();
+
+    // First pass: give each activated pixel the label of an earlier neighbour, noting
+    // when two labels meet
+    for x in 0..width {
+        for y in 0..height {
+            let activation = activation_map.get(x, y);
+            let seed_threshold = get_seed_threshold(x, y);
+            // Seeds always join, even if the grow threshold is above the seed one
+            let is_seed = activation >= seed_threshold;
+            let is_grown = activation >= get_grow_threshold(x, y);
+            if !is_seed && !is_grown {
+                continue
+            }
+            let mut label: Option<usize> = None;
+            for (x_offset, y_offset) in connectivity.get_previous_offsets() {
+                let (neighbour_x, neighbour_y) = (x as i64 + x_offset, y as i64 + y_offset);
+                if neighbour_x < 0 || neighbour_y < 0 || neighbour_y >= height as i64 {
+                    continue
+                }
+                let neighbour_label = provisional[get_index(neighbour_x as u32, neighbour_y as u32)];
+                if neighbour_label == usize::MAX {
+                    continue
+                }
+                match label {
+                    None => label = Some(neighbour_label),
+                    Some(label) => {
+                        let root = find_group(&mut label_parents, label);
+                        let neighbour_root = find_group(&mut label_parents, neighbour_label);
+                        // Lower labels are first in scan order, so stay the root
+                        label_parents[root.max(neighbour_root)] = root.min(neighbour_root);
+                    }
+                }
+            }
+            let label = label.unwrap_or_else(|| {
+                label_parents.push(label_parents.len());
+                is_seeded.push(false);
+                label_parents.len() - 1
+            });
+            if is_seed {
+                is_seeded[label] = true;
+            }
+            provisional[get_index(x, y)] = label;
+        }
+    }
+
+    // Second pass: resolve every label to its group, in scan order
+    for label in 0..label_parents.len() {
+        let root = find_group(&mut label_parents, label);
+        if is_seeded[label] {
+            is_seeded[root] = true;
+        }
+    }
+    let mut lines: Vec<Vec<(u32, u32)>> = Vec::new();
+    let mut line_indices: Vec<Option<usize>> = vec![None; label_parents.len()];
+    for x in 0..width {
+        for y in 0..height {
+            let label = provisional[get_index(x, y)];
+            if label == usize::MAX {
+                continue
+            }
+            let root = find_group(&mut label_parents, label);
+            if !is_seeded[root] {
+                continue
+            }
+            let line_index = *line_indices[root].get_or_insert_with(|| {
+                lines.push(Vec::new());
+                lines.len() - 1
+            });
+            lines[line_index].push((x, y));
+        }
+    }
+
+    // Drops tiny lines, renumbering the rest
+    let mut kept_indices: Vec<Option<usize>> = Vec::new();
+    let mut kept_lines: Vec<Vec<(u32, u32)>> = Vec::new();
+    for line in lines {
+        if line.len() > MIN_LINE_PIXELS {
+            kept_indices.push(Some(kept_lines.len()));
+            kept_lines.push(line);
+        } else {
+            kept_indices.push(None);
+        }
+    }
+    let labels: Vec<u32> = provisional.iter()
+        .map(|label| {
+            if *label == usize::MAX {
+                return 0
+            }
+            let root = find_group(&mut label_parents, *label);
+            return match line_indices[root].and_then(|index| kept_indices[index]) {
+                Some(index) => index as u32 + 1,
+                None => 0,
+            }
+        })
+        .collect();
+    return (label_image { width, height, labels }, kept_lines)
+}
+
+// First find lines: clusters of touching pixels with activation above threshold.
+// The activation map is left as it was.
+pub fn get_lines(activation_map: &activation_map, threshold: f32) -> Vec<Vec<(u32, u32)>> {
+    return get_lines_with(activation_map, |_, _| threshold, |_, _| threshold, connectivity::Eight)
+}
+
+// Two threshold (hysteresis) version of get_lines: lines only start at pixels above
+// seed_threshold, but grow through any above grow_threshold. Faint parts of an outline,
+// e.g. thin anti-aliased borders, then stay joined to the rest rather than splitting it.
+pub fn get_hysteresis_lines(
+    activation_map: &activation_map,
+    seed_threshold: f32,
+    grow_threshold: f32
+) -> Vec<Vec<(u32, u32)>> {
+    return get_lines_with(activation_map, |_, _| seed_threshold, |_, _| grow_threshold, connectivity::Eight)
+}
+
+// Most general form of get_lines, see label_components
+pub fn get_lines_with(
+    activation_map: &activation_map,
+    get_seed_threshold: impl Fn(u32, u32) -> f32,
+    get_grow_threshold: impl Fn(u32, u32) -> f32,
+    connectivity: connectivity
+) -> Vec<Vec<(u32, u32)>> {
+    return label_components(activation_map, get_seed_threshold, get_grow_threshold, connectivity).1
+}
+
+pub struct line {
+    pub pixels: Vec<(u32, u32)>,
+    pub bounds: rect, // Bounding box of pixels
+    // Tighter outlines for slanted lines, None until add_rotated_bounds is called
+    pub hull: Option<Vec<(u32, u32)>>, // Pixel corners, see get_convex_hull
+    pub rotated_bounds: Option<rotated_rect>,
+}
+
+impl line {
+    pub fn get_activation(&self) -> f32{
+        return self.pixels.len() as f32 / self.bounds.area() as f32;
+    }
+
+    // Finds the convex hull and minimum area rotated rectangle. Not done by get_lines_stats
+    // as it costs more than the bounding box and most UI is axis aligned.
+    pub fn add_rotated_bounds(&mut self) {
+        let hull = get_convex_hull(&self.pixels);
+        self.rotated_bounds = get_min_area_rect(&hull);
+        self.hull = Some(hull);
+    }
+}
+
+// Takes in a vector of points and inits a line which now includes extra stats
+pub fn get_lines_stats(lines_points: Vec<Vec<(u32, u32)>>) -> Vec<line> {
+    // Only the axis aligned bounds are found here, see line::add_rotated_bounds for tighter ones
+    let mut lines_stats: Vec<line> = Vec::new();
+    for line_points in lines_points {
+        // Lines from get_lines are never empty
+        let Some(bounds) = rect::from_points(&line_points) else { continue };
+        lines_stats.push(line {
+            pixels: line_points,
+            bounds,
+            hull: None,
+            rotated_bounds: None,
+        })
+    }
+    return lines_stats;
+}
+
+// Fraction of the seed threshold lines grow through, see get_hysteresis_lines.
+// 1.0 turns hysteresis off, e.g. 0.5 keeps faint borders joined.
+pub const HYSTERESIS_RATIO: f32 = 1.0;
+
+pub const AREA_THRESHOLD: u32 = 8;
+pub const LARGER_WIDTH_THRESHOLD: u32 = 8;
+// Minimum activation relative to size
+// Removes empty "box" elements.
+pub const ACTIVATION_THRESHOLD: f32 = 0.4;
+
+pub fn sanitise_lines(
+    lines: Vec<line>,
+    area_threshold: u32,
+    larger_width_threshold: u32,
+    activation_threshold: f32
+) -> Vec<line> {
+    let mut new_lines: Vec<line> = Vec::new();
+    for line in lines {
+        let activation: f32 = line.get_activation();
+        // Widths here are from the first pixel to the last, one less than the bounds
+        let larger_width = std::cmp::max(line.bounds.width, line.bounds.height) - 1;
+        if line.bounds.area() >= area_threshold
+            && larger_width >= larger_width_threshold
+            && activation >= activation_threshold {
+            new_lines.push(line)
+        }
+    }
+    return new_lines
+}
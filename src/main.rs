@@ -1,420 +1,518 @@
-use std::{io::Cursor, path::Ancestors, u8, collections::HashMap};
-use image::{DynamicImage, ImageBuffer, ImageReader, Pixel};
-
-struct activation_stats {
-    max: u8,
-    min: u8,
-    activation_count: u32, // No. pixels with activation > 0
-    avg_activation: f32, // Avg. activation for pixels with activation > 0
+use std::{fs, path::{Path, PathBuf}};
+use clap::builder::RangedU64ValueParser;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use image::{DynamicImage, ImageBuffer, ImageReader};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use nea_element_detection::activation::IMMEDIATE_NEIGHBOUR_WEIGHT;
+use nea_element_detection::color::color_difference;
+use nea_element_detection::draw::{draw_bounding_box, draw_line, draw_rotated_box};
+use nea_element_detection::edges::{edge_operator, BLUR_SIGMA, CANNY_HIGH_THRESHOLD, CANNY_LOW_THRESHOLD};
+use nea_element_detection::eval::{
+    evaluate, format_report, get_detections, labelled_image, load_dataset, load_images, EVAL_IOU_THRESHOLD
+};
+use nea_element_detection::frequency::get_layouts;
+use nea_element_detection::lines::{
+    connectivity, ACTIVATION_THRESHOLD, AREA_THRESHOLD, HYSTERESIS_RATIO, LARGER_WIDTH_THRESHOLD
+};
+use nea_element_detection::nms::{duplicate_preference, DUPLICATE_IOU_THRESHOLD, DUPLICATE_MAX_GAP};
+use nea_element_detection::ocr::{glyph_recognizer, ocr_error, text_recognizer};
+use nea_element_detection::pipeline::LINE_THRESHOLD;
+#[cfg(feature = "tesseract")]
+use nea_element_detection::ocr::tesseract_recognizer;
+use nea_element_detection::output;
+use nea_element_detection::profile::config_profile;
+use nea_element_detection::pyramid::{PYRAMID_IOU_THRESHOLD, PYRAMID_LEVELS, PYRAMID_SCALE};
+use nea_element_detection::text::{DIFFERENCE_COLOR_THRESH, TEXT_HIGH_FREQUENCY_SHARE};
+use nea_element_detection::threshold::{
+    threshold_mode, ADAPTIVE_OFFSET, ADAPTIVE_RADIUS, THRESHOLD_PERCENTILE, THRESHOLD_STD_COUNT
+};
+use nea_element_detection::tune::{
+    get_grid_configs, get_grid_size, get_random_configs, search_space, search_strategy, tune, tune_metric,
+    MAX_GRID_CONFIGS
+};
+use nea_element_detection::{detector, detector_config};
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum output_format {
+    Image, // Line image with bounding boxes, saved to the output directory
+    Json, // Document describing every element, printed to stdout
 }
 
-const IMMEDIATE_NEIGHBOUR_WEIGHT: f32 = 0.6; // Describes how immediate and unnimedate activation should impact overall
-// activation relative to each other see get_pixel_activation
-
-fn get_pixel_activation(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>, x: u32, y: u32) -> f32 {
-    // Creates a sort of brush, where immediate neighbours have more of an effect
-    // on the activation, and their neighbours have a slight effect.
-    let pixel = buffer.get_pixel(x, y);
-    let pixel_channels = pixel.channels();
-    let mut immediate_activation = 0.0; // Immediate neighbour's total activation
-    let mut unimmediate_activation = 0.0; // All other activation
-    let mut checked_no_immediate: usize = 0;
-    let mut checked_no_unimmediate: usize = 0;
-    for x_offset  in -2..=2 {
-            for y_offset in -2..=2 {
-                if x_offset == 0 && y_offset == 0 {
-                    continue
-                }
-                let offseted_x = (x as i32) + x_offset;
-                let offseted_y = (y as i32) + y_offset;
-                if offseted_x < 0
-                    || offseted_x >= buffer.width() as i32
-                    || offseted_y < 0
-                    || offseted_y >= buffer.height() as i32 {
-                    continue
-                }
-                let offseted_pixel = buffer.get_pixel(offseted_x as u32, offseted_y as u32);
-                let offseted_channels = offseted_pixel.channels();
-                
-                let mut difference: f32 = 0.0;
-                for color in 0..pixel_channels.len() {
-                    difference += 
-                        ((pixel_channels[color] as i32-offseted_channels[color] as i32) as f32).abs()
-                        / pixel_channels.len() as f32; // Adjusts for no. channels
-                }
-                if x_offset == -2
-                || x_offset == 2
-                || y_offset == -2
-                || y_offset == 2 {
-                    unimmediate_activation += difference;
-                    checked_no_unimmediate += 1;
-                } else {
-                    immediate_activation += difference;
-                    checked_no_immediate += 1;
-                }
-            }
-        }
-        immediate_activation /= checked_no_immediate as f32;
-        unimmediate_activation /= checked_no_unimmediate as f32;
-    let activation = immediate_activation * IMMEDIATE_NEIGHBOUR_WEIGHT + unimmediate_activation * (1.0-IMMEDIATE_NEIGHBOUR_WEIGHT); 
-    return activation;
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ocr_engine {
+    Glyph, // Built-in template matching, see glyph_recognizer
+    #[cfg(feature = "tesseract")]
+    Tesseract, // Local tesseract install, see tesseract_recognizer
+    None, // Text is left empty
 }
 
-fn difference_filter(
-    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
-    difference_function: &dyn Fn(&ImageBuffer<image::Rgb<u8>, Vec<u8>>, u32, u32) -> f32
-) -> ImageBuffer<image::Rgb<u8>, Vec<u8>> {
-    // Creates an "activation buffer" which will store the values of
-    // the "activation" - how each pixel compares to its surroundings
-    let mut filter_buffer = image::RgbImage::new(buffer.width(), buffer.height());
-    for (x, y, curr_pixel) in buffer.enumerate_pixels() {
-        let difference = difference_function(&buffer, x, y);
-        let difference_pixel = filter_buffer.get_pixel_mut(x, y);
-        *difference_pixel = image::Rgb([difference as u8, difference as u8, difference as u8]);
-    }
-    return filter_buffer
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum report_format {
+    Text, // Aligned tables
+    Json,
 }
 
-fn get_activation_stats(buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>) -> activation_stats {
-    // Calculates useful statistics for a given buffer, see activation_stats
-    let mut max: u8 = 0;
-    let mut min: u8 = 255;
-    let mut activation_count: u32 = 0;
-    let mut total_activation: u32 = 0;
+#[derive(Parser)]
+#[command(about = "Detects UI elements in screenshots")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct args {
+    #[command(subcommand)]
+    command: Option<command>,
 
-    for (x, y, pixel) in buffer.enumerate_pixels() {
-        if pixel[0] > max {
-            max = pixel[0]
-        }
-        if pixel[0] < min {
-            min = pixel[0]
-        }
-        if pixel[0] > 0 {
-            activation_count += 1;
-            total_activation += pixel[0] as u32
-        }
-    }
-    let avg_activation: f32 = total_activation as f32/activation_count as f32;
-    return activation_stats{max, min, activation_count, avg_activation}
-}
-fn get_surrounding_pixels(x: u32, y: u32, width: u32, height: u32) -> Vec<(u32, u32)>{
-    let mut pixels: Vec<(u32, u32)> = Vec::new();
-    for x_offs in -1..=1 {
-        for y_offs in -1..=1 {
-            if x_offs == 0 && y_offs == 0 {
-                continue
-            }
-            let offs_x = x as i32 + x_offs;
-            let offs_y = y as i32 + y_offs;
-
-            if offs_x < 0
-                || offs_x >= width as i32
-                || offs_y < 0
-                || offs_y>= height as i32 {
-                continue
-            }
-            pixels.push((offs_x as u32, offs_y as u32));
-        }
-    }
-    return pixels;
-}
+    /// Images to process, either paths or glob patterns (e.g. "screenshots/*.png")
+    #[arg(required = true)]
+    inputs: Vec<String>,
 
-// First find lines: start at some point of activation, create a vector of all points traversed
-// Iterate through every point which has activation above threshold. Remove all points which have been visited
-fn get_lines(buffer: &mut ImageBuffer<image::Rgb<u8>, Vec<u8>>, threshold: u8) -> Vec<Vec<(u32, u32)>> {
-    // Only values above the threshold will be considered for being part of lines
-    let mut lines: Vec<Vec<(u32, u32)>> = Vec::new();
-    // Adds all activated pixels to lines (clusters)
-    for x in 0..buffer.width() {
-        for y in 0..buffer.height() {
-            let pixel = buffer.get_pixel_mut(x, y);
-            if pixel[0] >= threshold {
-                // Start line creation
-                let mut line: Vec<(u32, u32)> = vec![(x, y)]; // Reps a cluster of activated pixels
-                let mut coords_to_be_checked: Vec<(u32, u32)> = vec![(x, y)]; // Stack of positions of ACTIVATED pixels which need to have surroundings searched
-                
-                // Deactivates starting pixel see line 
-                *pixel = image::Rgb([0,0,0]);
-
-                // While there are still activated coords which haven't had their neighbours checked
-                while let Some(check_coord) = coords_to_be_checked.pop() {
-                    // Immediate neighbours to current pixel being checked
-                    let mut surrounding_pixels = get_surrounding_pixels(
-                        check_coord.0,
-                        check_coord.1,
-                        buffer.width(),
-                        buffer.height()
-                    );
-                    for pixel_index in 0..surrounding_pixels.len() {
-                        let surrounding_pixel_x = surrounding_pixels[pixel_index].0;
-                        let surrounding_pixel_y = surrounding_pixels[pixel_index].1;
-
-                        let surrounding_pixel= buffer
-                            .get_pixel_mut(surrounding_pixel_x, surrounding_pixel_y);
-                        if surrounding_pixel.channels()[0] >= threshold {
-                            coords_to_be_checked.push(surrounding_pixels[pixel_index]);
-
-                            line.push(surrounding_pixels[pixel_index]);
-
-                            // Ensures each pixel is only part of one line, once, by "deactivating" it
-                            *surrounding_pixel = image::Rgb([0,0,0])
-                        }
-                    }
-                }
-                // Ensures few pixel lines not added
-                // Likely just artefacts -> invisible, not pressable buttons
-                if line.len() > 4 {
-                    // Line creation finished, add line to lines
-                    lines.push(line);
-                }
-                
-            }
-        }
-    }
-    return lines
+    /// Directory the line images are written to
+    #[arg(short, long, default_value = ".")]
+    output_dir: PathBuf,
+
+    /// What to output for each image
+    #[arg(short, long, value_enum, default_value_t = output_format::Image)]
+    format: output_format,
+
+    /// Look for grids and lists (repeated rows or columns) in the image and its containers.
+    /// Only reported by --format json, so skipped for images
+    #[arg(long)]
+    layouts: bool,
+
+    #[command(flatten)]
+    detector: detector_args,
 }
 
-struct line {
-    pixels: Vec<(u32, u32)>,
-    top_left: (u32, u32), // Basic quadrilateral
-    top_right: (u32, u32),
-    bottom_left: (u32, u32),
-    bottom_right: (u32, u32),
-    area: u32,
+#[derive(Subcommand)]
+enum command {
+    /// Runs the detector over labelled images and reports precision, recall, F1 and mAP
+    Eval(eval_args),
+    /// Searches for the detector settings scoring best over labelled images
+    Tune(tune_args),
 }
 
-impl line {
-    fn get_activation(&self) -> f32{
-        return self.pixels.len() as f32 / self.area as f32;
-    }
+#[derive(clap::Args)]
+struct eval_args {
+    /// Ground truth files, either paths or glob patterns: COCO JSON, or the JSON written by
+    /// the synthetic generator
+    #[arg(required = true)]
+    datasets: Vec<String>,
+
+    /// Overlap (intersection over union) a detection needs with a labelled element to match
+    /// it, above 0 and at most 1
+    #[arg(long, default_value_t = EVAL_IOU_THRESHOLD, value_parser = parse_iou_threshold)]
+    iou_threshold: f32,
+
+    /// How the report is printed
+    #[arg(short, long, value_enum, default_value_t = report_format::Text)]
+    format: report_format,
+
+    #[command(flatten)]
+    detector: detector_args,
 }
 
-// Takes in a vector of points and inits a line which now includes extra stats
-fn get_lines_stats(lines_points: Vec<Vec<(u32, u32)>>) -> Vec<line> {
-    // In order to find a top left corner, create a straight y = x + c line at rightmost
-    // point, then remove all points which are beneath this point. Continue to increase
-    // c until only one point is left.
-    // Do the same for all 4 points. -> MAY IMPLEMENT LATER
-    // This process may be computationally intense. Could switch out for simpler
-    // method, for example for top left point, use y of top point and x leftmost point
-    let mut lines_stats: Vec<line> = Vec::new();
-    for line_points in lines_points {
-        let mut rightmost_point: (u32, u32) = (0, 0);
-        let mut leftmost_point: (u32, u32) = (99999, 0);
-        let mut top_point: (u32, u32) = (0, 0);
-        let mut bottom_point: (u32, u32) = (0, 99999);
-        for point in &line_points[..] {
-            if point.0 > rightmost_point.0 {
-                rightmost_point = (point.0, point.1)
-            }
-            if point.0 < leftmost_point.0 { // Can't be else if
-                leftmost_point = (point.0, point.1)
-            }
-            if point.1 > top_point.1 {
-                top_point = (point.0, point.1)
-            }
-            if point.1 < bottom_point.1 {
-                bottom_point = (point.0, point.1)
-            }
-        }
-        let dx = rightmost_point.0 - leftmost_point.0; // Edge - edge
-        let dy = top_point.1 - bottom_point.1; // Doesn't include one of the edges
-        let area: u32 = (dx+1)
-            * (dy+1);
-
-        lines_stats.push(line {
-            pixels: line_points,
-            top_left: (leftmost_point.0, top_point.1),
-            top_right: (rightmost_point.0, top_point.1),
-            bottom_left: (leftmost_point.0, bottom_point.1),
-            bottom_right: (rightmost_point.0, bottom_point.1),
-            area: area,
-        })
+// Any overlap above none can match, 0 would match detections to elements they don't touch
+fn parse_iou_threshold(value: &str) -> Result<f32, String> {
+    let iou_threshold = value.parse::<f32>().map_err(|error| error.to_string())?;
+    // Written so NaN fails too
+    if iou_threshold > 0.0 && iou_threshold <= 1.0 {
+        return Ok(iou_threshold)
     }
-    return lines_stats;
+    return Err("expected above 0 and at most 1".to_string())
 }
 
-const AREA_THRESHOLD: u32 = 8;
-const LARGER_WIDTH_THRESHOLD: u32 = 8;
-// Minimum activation relative to size
-// Removes empty "box" elements.
-const ACTIVATION_THRESHOLD: f32 = 0.4;
-
-fn sanitise_lines(lines: Vec<line>) -> Vec<line> {
-    let mut new_lines: Vec<line> = Vec::new();
-    for line in lines {
-        let width = line.top_right.0 - line.top_left.0;
-        let height = line.top_left.1 - line.bottom_left.1;
-        let activation: f32 = line.get_activation();
-        println!("{} {} {} {} {}", width, height, line.area, line.pixels.len(), activation);
-        if line.area >= AREA_THRESHOLD 
-            && std::cmp::max(width, height) >= LARGER_WIDTH_THRESHOLD
-            && activation >= ACTIVATION_THRESHOLD {
-            new_lines.push(line)
-        }
-    }
-    return new_lines
+#[derive(clap::Args)]
+struct tune_args {
+    /// Ground truth files, as for eval
+    #[arg(required = true)]
+    datasets: Vec<String>,
+
+    /// Overlap (intersection over union) a detection needs with a labelled element to match
+    /// it, above 0 and at most 1
+    #[arg(long, default_value_t = EVAL_IOU_THRESHOLD, value_parser = parse_iou_threshold)]
+    iou_threshold: f32,
+
+    /// How settings are chosen: grid (every combination) or random
+    #[arg(long, default_value_t = search_strategy::Random)]
+    strategy: search_strategy,
+
+    /// Number of settings tried by random search
+    #[arg(long, default_value_t = 50)]
+    samples: usize,
+
+    /// Values tried for each setting by grid search, up to steps^6 settings are tried in all
+    /// and at most 10000 are allowed
+    #[arg(long, default_value_t = 3, value_parser = RangedU64ValueParser::<usize>::new().range(1..=MAX_GRID_CONFIGS as u64))]
+    grid_steps: usize,
+
+    /// Seed for random search
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Score being maximised: f1 or map (mean average precision)
+    #[arg(long, default_value_t = tune_metric::F1)]
+    metric: tune_metric,
+
+    /// File the best settings are written to, load them with --config
+    #[arg(long, default_value = "tuned_config.json")]
+    output: PathBuf,
+
+    /// Settings which aren't searched are taken from these
+    #[command(flatten)]
+    detector: detector_args,
 }
 
-struct text_line<'a> {
-    line: &'a line,
-    stroke_color: image::Rgb<u8>,
-    text: String
+// Everything which changes what the detector finds
+#[derive(clap::Args)]
+struct detector_args {
+    /// Detector settings file, TOML or JSON by extension, e.g. written by tune. Flags given as
+    /// well override it
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Settings to start from: default, light-desktop, dark-mobile or photo-of-screen. Flags
+    /// given as well override it. A config file names its own with a profile key
+    #[arg(long, conflicts_with = "config")]
+    profile: Option<config_profile>,
+
+    /// Engine used to read text
+    #[arg(long, value_enum, default_value_t = ocr_engine::Glyph)]
+    ocr: ocr_engine,
+
+    /// Minimum activation for a pixel to be part of a line
+    #[arg(long, default_value_t = LINE_THRESHOLD)]
+    line_threshold: f32,
+
+    /// How the line threshold is chosen: fixed (--line-threshold), otsu, percentile, mean_std or adaptive
+    #[arg(long, default_value_t = threshold_mode::Fixed)]
+    threshold_mode: threshold_mode,
+
+    /// Percentile of activated pixels used as the threshold, percentile mode only
+    #[arg(long, default_value_t = THRESHOLD_PERCENTILE)]
+    threshold_percentile: f32,
+
+    /// k in mean + k * standard deviation, mean_std mode only
+    #[arg(long, default_value_t = THRESHOLD_STD_COUNT)]
+    threshold_std_count: f32,
+
+    /// Radius of the window averaged around each pixel, adaptive mode only
+    #[arg(long, default_value_t = ADAPTIVE_RADIUS)]
+    adaptive_radius: u32,
+
+    /// Added to the local average, adaptive mode only
+    #[arg(long, default_value_t = ADAPTIVE_OFFSET)]
+    adaptive_offset: f32,
+
+    /// Lines start above the threshold but grow through pixels above this fraction of it,
+    /// keeping faint borders joined. 1.0 turns this off
+    #[arg(long, default_value_t = HYSTERESIS_RATIO)]
+    hysteresis_ratio: f32,
+
+    /// Which neighbouring pixels join lines: four (4) or eight (8, includes diagonals)
+    #[arg(long, default_value_t = connectivity::Eight)]
+    connectivity: connectivity,
+
+    /// Stretch activations so the largest is 255 before thresholding, for low contrast images.
+    /// =false turns it off when a config file or profile turns it on
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_value_t = false, default_missing_value = "true")]
+    normalise_activation: bool,
+
+    /// How activation is found: brush, sobel, scharr, laplacian_of_gaussian (or log) or canny
+    #[arg(long, default_value_t = edge_operator::Brush)]
+    edge_operator: edge_operator,
+
+    /// Weight of immediate neighbours relative to their neighbours, see get_pixel_activation
+    #[arg(long, default_value_t = IMMEDIATE_NEIGHBOUR_WEIGHT)]
+    immediate_neighbour_weight: f32,
+
+    /// How colours are compared by the brush and when grouping a line's colours:
+    /// rgb, cie76, ciede2000 or oklab
+    #[arg(long, default_value_t = color_difference::Rgb)]
+    color_difference: color_difference,
+
+    /// Colours closer than this are treated as the same when grouping a line's colours and
+    /// matching text stroke colours
+    #[arg(long, default_value_t = DIFFERENCE_COLOR_THRESH)]
+    difference_color_threshold: f32,
+
+    /// Gaussian blur applied before the laplacian_of_gaussian and canny operators
+    #[arg(long, default_value_t = BLUR_SIGMA)]
+    blur_sigma: f32,
+
+    /// Weakest edge canny keeps when joined to a strong one
+    #[arg(long, default_value_t = CANNY_LOW_THRESHOLD)]
+    canny_low_threshold: f32,
+
+    /// Weakest edge canny keeps on its own
+    #[arg(long, default_value_t = CANNY_HIGH_THRESHOLD)]
+    canny_high_threshold: f32,
+
+    /// Minimum bounding box area of a line
+    #[arg(long, default_value_t = AREA_THRESHOLD)]
+    area_threshold: u32,
+
+    /// Minimum size of a line's larger side
+    #[arg(long, default_value_t = LARGER_WIDTH_THRESHOLD)]
+    larger_width_threshold: u32,
+
+    /// Minimum fraction of a line's bounding box which must be activated
+    #[arg(long, default_value_t = ACTIVATION_THRESHOLD)]
+    activation_threshold: f32,
+
+    /// Also look for lines in this many smaller copies of the image (including the original),
+    /// finding large panels the brush misses at full size. 1 only uses the original
+    #[arg(long, default_value_t = PYRAMID_LEVELS)]
+    pyramid_levels: u32,
+
+    /// Size of each pyramid level relative to the one before it, between 0 and 1
+    #[arg(long, default_value_t = PYRAMID_SCALE)]
+    pyramid_scale: f32,
+
+    /// Overlap (intersection over union) at which a line from a smaller level is dropped in
+    /// favour of one from a larger level
+    #[arg(long, default_value_t = PYRAMID_IOU_THRESHOLD)]
+    pyramid_iou_threshold: f32,
+
+    /// Merge or drop overlapping lines, e.g. a button's focus ring and border. =false turns it
+    /// off when a config file or profile turns it on
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_value_t = false, default_missing_value = "true")]
+    suppress_duplicates: bool,
+
+    /// Overlap (intersection over union) at which two lines are duplicates
+    #[arg(long, default_value_t = DUPLICATE_IOU_THRESHOLD)]
+    duplicate_iou_threshold: f32,
+
+    /// Nested lines are also duplicates if no side is further than this many pixels apart
+    #[arg(long, default_value_t = DUPLICATE_MAX_GAP)]
+    duplicate_max_gap: u32,
+
+    /// Which duplicate is kept: outer, inner or merge (one line with both)
+    #[arg(long, default_value_t = duplicate_preference::Outer)]
+    duplicate_preference: duplicate_preference,
+
+    /// Minimum share of a line's spectrum above the high frequency cutoff for it to be text,
+    /// ruling out smooth gradients. 0 turns this off, text is usually above 0.1
+    #[arg(long, default_value_t = TEXT_HIGH_FREQUENCY_SHARE)]
+    text_high_frequency_share: f32,
+
+    /// Also find a convex hull and minimum area rotated box for each element. =false turns it
+    /// off when a config file or profile turns it on
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_value_t = false, default_missing_value = "true")]
+    rotated_bounds: bool,
 }
 
-const DIFFERENCE_COLOR_THRESH: f32 = 30.0;
-fn get_line_colors(line: &line, buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>)
-    -> HashMap<image::Rgb<u8>, u32> {
-    let mut color_freqs: HashMap<image::Rgb<u8>, u32> = HashMap::new();
-    for pixel in &line.pixels {
-        let pixel = buffer.get_pixel(pixel.0, pixel.1);
-        let curr_color = pixel.channels();
-        let mut match_found = false;
-        for (i, other_color) in color_freqs.keys().enumerate() {
-            let mut difference_squared: f32 = 0.0;
-            for channel in 0..curr_color.len() {
-                difference_squared += 
-                    (curr_color[channel] as i32 - other_color[channel] as i32).pow(2) as f32;
-            }
-            let difference = difference_squared.sqrt();
-            if difference <= DIFFERENCE_COLOR_THRESH {
-                match_found = true;
-                *color_freqs.entry(other_color.clone()).or_insert(0) += 1;
-                break
-            }
+// Expands every input into the paths it refers to, so both plain paths and globs can be given
+fn get_input_paths(inputs: &[String]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for input in inputs {
+        let mut matched = false;
+        for path in glob::glob(input)? {
+            paths.push(path?);
+            matched = true;
         }
-        if !match_found {
-            color_freqs.insert(image::Rgb::<u8>([curr_color[0], curr_color[1], curr_color[2]]), 1);
+        if !matched {
+            return Err(format!("no files match \"{}\"", input).into());
         }
     }
-    return color_freqs;
+    return Ok(paths);
 }
 
-fn get_most_common_color(color_freqs: &HashMap<image::Rgb<u8>, u32>) -> image::Rgb<u8> {
-    let mut most_common = image::Rgb::<u8>([0, 0, 0]);
-    let mut highest_freq: u32 = 0;
-    for (color, freq) in color_freqs.iter() {
-        if *freq > highest_freq {
-            most_common = *color;
-            highest_freq = *freq;
+impl detector_args {
+    // Settings from the config file or profile, with any flags given on top
+    fn get_config(&self, matches: &ArgMatches) -> Result<detector_config, Box<dyn std::error::Error>> {
+        let base = match &self.config {
+            Some(path) => detector_config::load(path)?,
+            None => self.profile.unwrap_or(config_profile::Default).get_config(),
+        };
+        // Flags are named after the config fields, so can be swapped in by name
+        let mut config = serde_json::to_value(base)?;
+        if let serde_json::Value::Object(flag_values) = serde_json::to_value(self.get_flags_config())? {
+            for (name, value) in flag_values {
+                if matches.value_source(&name) == Some(ValueSource::CommandLine) {
+                    config[name] = value;
+                }
+            }
         }
+        let config: detector_config = serde_json::from_value(config)?;
+        config.validate()?;
+        return Ok(config);
     }
-    return most_common;
-}
 
-// Returns all lines it suspects to contain text, by examining the original image
-fn get_text_lines<'a>(lines: &'a Vec<line>, img_buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>) -> Vec<text_line<'a>> {
-    // List containing all lines which are text
-    // Currently weak
-    // TODO makes this function more accurate
-    let mut text_lines: Vec<text_line> = Vec::new();
-
-    for line in lines {
-        let color_freqs = get_line_colors(line, img_buffer);
-        if color_freqs.keys().len() == 2 { // If the line only contains 2 colors
-            let stroke_color = get_most_common_color(&color_freqs);
-            text_lines.push(
-                text_line {
-                    line: line,
-                    stroke_color: stroke_color,
-                    text: "".to_string(), 
-                }
-            )
+    fn get_flags_config(&self) -> detector_config {
+        return detector_config {
+            line_threshold: self.line_threshold,
+            threshold_mode: self.threshold_mode,
+            threshold_percentile: self.threshold_percentile,
+            threshold_std_count: self.threshold_std_count,
+            adaptive_radius: self.adaptive_radius,
+            adaptive_offset: self.adaptive_offset,
+            hysteresis_ratio: self.hysteresis_ratio,
+            connectivity: self.connectivity,
+            normalise_activation: self.normalise_activation,
+            edge_operator: self.edge_operator,
+            immediate_neighbour_weight: self.immediate_neighbour_weight,
+            color_difference: self.color_difference,
+            difference_color_threshold: self.difference_color_threshold,
+            blur_sigma: self.blur_sigma,
+            canny_low_threshold: self.canny_low_threshold,
+            canny_high_threshold: self.canny_high_threshold,
+            area_threshold: self.area_threshold,
+            larger_width_threshold: self.larger_width_threshold,
+            activation_threshold: self.activation_threshold,
+            pyramid_levels: self.pyramid_levels,
+            pyramid_scale: self.pyramid_scale,
+            pyramid_iou_threshold: self.pyramid_iou_threshold,
+            suppress_duplicates: self.suppress_duplicates,
+            duplicate_iou_threshold: self.duplicate_iou_threshold,
+            duplicate_max_gap: self.duplicate_max_gap,
+            duplicate_preference: self.duplicate_preference,
+            text_high_frequency_share: self.text_high_frequency_share,
+            rotated_bounds: self.rotated_bounds,
         }
     }
-    text_lines
-}
 
-//fn transcribe_text_lines(lines: Vec<Vec<(u32, u32)>>) -> Vec<Vec<(u32, u32, String)>> {}
+    fn get_recognizer(&self) -> Result<Option<Box<dyn text_recognizer>>, ocr_error> {
+        return Ok(match self.ocr {
+            ocr_engine::Glyph => Some(Box::new(glyph_recognizer::default())),
+            #[cfg(feature = "tesseract")]
+            ocr_engine::Tesseract => Some(Box::new(tesseract_recognizer::new()?)),
+            ocr_engine::None => None,
+        })
+    }
+
+    fn get_detector(&self, matches: &ArgMatches) -> Result<detector, Box<dyn std::error::Error>> {
+        return Ok(detector::new(self.get_config(matches)?).with_recognizer(self.get_recognizer()?))
+    }
+}
 
-// Takes in a line and creates an image which contains the pixels from that line's bounding box
-// fn line_to_image
+fn process_image(
+    img_path: &Path,
+    detector: &detector,
+    args: &args
+) -> Result<output::image_output, Box<dyn std::error::Error>> {
+    let img = ImageReader::open(img_path)?.decode()?;
+    let buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>> = DynamicImage::into_rgb8(img);
 
-fn draw_line(mut buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>>, line: &line)
-    -> ImageBuffer::<image::Rgb<u8>, Vec<u8>> {
-        for point in 0..line.pixels.len() {
-            // println!("{} {}", lines_stats[l].points[point].0, lines_stats[l].points[point].1);
-            let x = line.pixels[point].0;
-            let y = line.pixels[point].1;
-            let pixel = buffer.get_pixel_mut(x, y);
-            *pixel = image::Rgb([255,255,255]);
+    let elements = detector.try_detect(&buffer)?;
+    let image_output = output::image_output {
+        path: img_path.display().to_string(),
+        width: buffer.width(),
+        height: buffer.height(),
+        elements: output::get_elements_output(&elements),
+        // Line images have nowhere to show layouts, so they aren't worth the FFTs
+        layouts: (args.layouts && args.format == output_format::Json).then(|| get_layouts(&buffer, &elements)),
+    };
+
+    if args.format == output_format::Image {
+        let mut line_buffer= image::RgbImage::new(buffer.width(), buffer.height());
+        for element in &elements {
+            line_buffer = draw_line(line_buffer, &element.line);
+            line_buffer = draw_bounding_box(line_buffer, &element.line);
+            line_buffer = draw_rotated_box(line_buffer, &element.line);
         }
-        return buffer
-    }
 
-fn draw_bounding_box(mut buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>>, line: &line) 
-    -> ImageBuffer::<image::Rgb<u8>, Vec<u8>> {
-    for x in line.top_left.0
-        ..=line.top_right.0 {
-        let pixel = buffer.get_pixel_mut(x, line.top_left.1);
-        *pixel = image::Rgb([0,255,0]);
+        let file_name = img_path.file_name().ok_or("input has no file name")?;
+        let output_path = args.output_dir.join("line_".to_string() + &file_name.to_string_lossy());
+        line_buffer.save(output_path)?;
     }
-    for x in line.bottom_left.0
-        ..=line.bottom_right.0 {
-        let pixel = buffer.get_pixel_mut(x, line.bottom_left.1);
-        *pixel = image::Rgb([0,255,0]);
+    Ok(image_output)
+}
+
+fn get_dataset(inputs: &[String]) -> Result<Vec<labelled_image>, Box<dyn std::error::Error>> {
+    let mut dataset: Vec<labelled_image> = Vec::new();
+    for path in get_input_paths(inputs)? {
+        dataset.extend(load_dataset(&path)?);
     }
-    for y in line.bottom_left.1
-        ..=line.top_left.1 {
-        let pixel = buffer.get_pixel_mut(line.top_left.0, y);
-        *pixel = image::Rgb([0,255,0]);
+    return Ok(dataset);
+}
+
+fn run_eval(args: &eval_args, matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let dataset = get_dataset(&args.datasets)?;
+    let images = load_images(&dataset)?;
+    let detector = args.detector.get_detector(matches)?;
+    let mut detections = Vec::new();
+    for (labelled, buffer) in dataset.iter().zip(&images) {
+        eprintln!("{}", labelled.path.display());
+        detections.push(get_detections(&detector, buffer));
     }
-    for y in line.bottom_right.1
-        ..=line.top_right.1 {
-        let pixel = buffer.get_pixel_mut(line.top_right.0, y);
-        *pixel = image::Rgb([0,255,0]);
+    let report = evaluate(&dataset, &detections, args.iou_threshold);
+    match args.format {
+        report_format::Text => println!("{}", format_report(&report)),
+        report_format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
     }
-    return buffer
+    Ok(())
 }
 
-fn get_line_images(lines: &Vec<line>, image_buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>>) -> Vec<ImageBuffer<image::Rgb<u8>, Vec<u8>>> {
-    let mut image_lines: Vec<ImageBuffer<image::Rgb<u8>, Vec<u8>>> = Vec::new();
-    for line in lines {
-        let line_box_width = line.top_right.0 - line.top_left.0;
-        let line_box_height = line.top_left.0 - line.bottom_left.0;
-        let image_line = image::imageops::crop_imm(
-            &image_buffer, 
-            line.top_left.0,
-            line.top_left.1,
-            line_box_width,
-            line_box_height
-        ).to_image();
-        
-        image_lines.push(image_line);
-        
+fn run_tune(args: &tune_args, matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let base = args.detector.get_config(matches)?;
+    let space = search_space::default();
+    // Checked before the dataset is loaded, as that can take a while
+    let grid_size = get_grid_size(&base, &space, args.grid_steps);
+    if args.strategy == search_strategy::Grid && grid_size > MAX_GRID_CONFIGS {
+        return Err(format!(
+            "--grid-steps {} would try {} settings, more than {}; use fewer steps or --strategy random",
+            args.grid_steps, grid_size, MAX_GRID_CONFIGS
+        ).into());
     }
-    return image_lines;
+    let dataset = get_dataset(&args.datasets)?;
+    let images = load_images(&dataset)?;
+    let mut candidates = match args.strategy {
+        search_strategy::Grid => get_grid_configs(&base, &space, args.grid_steps),
+        search_strategy::Random => get_random_configs(&base, &space, args.samples, &mut StdRng::seed_from_u64(args.seed)),
+    };
+    // The starting settings are tried too, so the result is never worse
+    candidates.insert(0, base);
+    let candidate_count = candidates.len();
+    let best = tune(&dataset, &images, candidates, args.iou_threshold, args.metric, |i, score| {
+        eprintln!("{}/{}: {} {:.4}", i + 1, candidate_count, args.metric, score);
+    }).ok_or("nothing to tune")?;
+    best.config.save(&args.output)?;
+    println!("{}", format_report(&best.report));
+    println!("Best {} {:.4}, settings written to {}", args.metric, best.score, args.output.display());
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>>{
-    let img_path = "image.png";
-    let img = ImageReader::open(img_path)?.decode()?;
-    let buffer: ImageBuffer<image::Rgb<u8>, Vec<u8>> = DynamicImage::into_rgb8(img);
-    
-    let mut activation_buffer = difference_filter(&buffer, &get_pixel_activation);
-    let stats = get_activation_stats(&activation_buffer);
-    println!(
-        "Max: {} Min: {} Activation count: {} Avg activation: {}",
-        stats.max, 
-        stats.min, 
-        stats.activation_count, 
-        stats.avg_activation
-    );
-    let line_threshold = 15;
-    let lines = get_lines(&mut activation_buffer, line_threshold);
-
-    let lines_stats = get_lines_stats(lines);
-    let lines_stats = sanitise_lines(lines_stats);
-
-    let mut line_buffer= image::RgbImage::new(activation_buffer.width(), activation_buffer.height());
-    for l in 0..lines_stats.len() {
-        line_buffer = draw_line(line_buffer, &lines_stats[l]);
-        line_buffer = draw_bounding_box(line_buffer, &lines_stats[l]);
+fn run(args: args, matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    match (&args.command, matches.subcommand()) {
+        (Some(command::Eval(eval_args)), Some((_, eval_matches))) => return run_eval(eval_args, eval_matches),
+        (Some(command::Tune(tune_args)), Some((_, tune_matches))) => return run_tune(tune_args, tune_matches),
+        _ => {},
+    }
+    let img_paths = get_input_paths(&args.inputs)?;
+    // JSON goes to stdout, so only line images need somewhere to go
+    if args.format == output_format::Image {
+        fs::create_dir_all(&args.output_dir)?;
+    }
+    let detector = args.detector.get_detector(matches)?;
+    let mut images_output: Vec<output::image_output> = Vec::new();
+    // A bad image is reported and skipped rather than losing the rest of the batch
+    let mut failed: usize = 0;
+    for img_path in &img_paths {
+        eprintln!("{}", img_path.display());
+        match process_image(img_path, &detector, &args) {
+            Ok(image_output) => images_output.push(image_output),
+            Err(error) => {
+                eprintln!("Error: {}: {}", img_path.display(), error);
+                failed += 1;
+            },
+        }
+    }
+    if args.format == output_format::Json {
+        println!("{}", serde_json::to_string_pretty(&images_output)?);
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} images failed", failed, img_paths.len()).into());
     }
-    
-    let mut line_images = get_line_images(&lines_stats, buffer);
-
-
-    line_buffer.save("line_".to_string() + img_path).unwrap();
     Ok(())
-}
\ No newline at end of file
+}
+
+fn main() {
+    // Errors are printed with Display rather than Debug, so they read as messages
+    // Matches are kept to tell which flags were given, see detector_args::get_config
+    let matches = args::command().get_matches();
+    let args = args::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    if let Err(error) = run(args, &matches) {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
+}
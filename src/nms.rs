@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use crate::bounds::rect;
+use crate::lines::{get_lines_stats, line};
+
+// Which of two duplicate lines is kept, e.g. a button's focus ring (outer) and border (inner)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum duplicate_preference {
+    Outer, // The larger box
+    Inner, // The smaller box
+    Merge, // One line with the pixels of both
+}
+
+pub const DUPLICATE_PREFERENCES: [duplicate_preference; 3] = [
+    duplicate_preference::Outer,
+    duplicate_preference::Inner,
+    duplicate_preference::Merge,
+];
+
+impl duplicate_preference {
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            duplicate_preference::Outer => "outer",
+            duplicate_preference::Inner => "inner",
+            duplicate_preference::Merge => "merge",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<duplicate_preference> {
+        let name = name.trim().to_lowercase();
+        return DUPLICATE_PREFERENCES.into_iter().find(|preference| preference.get_name() == name)
+    }
+}
+
+impl std::fmt::Display for duplicate_preference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.get_name())
+    }
+}
+
+impl std::str::FromStr for duplicate_preference {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<duplicate_preference, String> {
+        return duplicate_preference::from_name(name).ok_or_else(|| {
+            let names: Vec<&str> = DUPLICATE_PREFERENCES.iter().map(|preference| preference.get_name()).collect();
+            format!("unknown duplicate preference \"{}\", expected one of: {}", name, names.join(", "))
+        })
+    }
+}
+
+// Lines overlapping by at least this (intersection over union) are duplicates
+pub const DUPLICATE_IOU_THRESHOLD: f32 = 0.6;
+// Nested lines are also duplicates if every side of the inner box is within this many
+// pixels of the outer one. Catches small elements, where a 2px ring alone drops the IoU a lot.
+pub const DUPLICATE_MAX_GAP: u32 = 2;
+
+// Largest distance between the sides of an outer box and a box inside it, None if not nested
+fn get_nested_gap(a: &rect, b: &rect) -> Option<u32> {
+    let (outer, inner) = if a.contains(b) {
+        (a, b)
+    } else if b.contains(a) {
+        (b, a)
+    } else {
+        return None
+    };
+    let gaps = [
+        inner.x - outer.x,
+        inner.y - outer.y,
+        outer.right() - inner.right(),
+        outer.bottom() - inner.bottom(),
+    ];
+    return gaps.into_iter().max()
+}
+
+// True if the two boxes are outlines of the same element
+pub fn is_duplicate(a: &rect, b: &rect, iou_threshold: f32, max_gap: u32) -> bool {
+    if a.get_iou(b) >= iou_threshold {
+        return true
+    }
+    return get_nested_gap(a, b).is_some_and(|gap| gap <= max_gap)
+}
+
+fn merge_line_pair(kept: line, duplicate: line) -> line {
+    let mut pixels: Vec<(u32, u32)> = kept.pixels;
+    pixels.extend(duplicate.pixels);
+    // Lines from different pyramid levels can share pixels
+    pixels.sort_unstable();
+    pixels.dedup();
+    return get_lines_stats(vec![pixels]).remove(0)
+}
+
+// Non-maximum suppression for lines which are duplicates of each other (see is_duplicate).
+// Lines are visited largest first for Outer and Merge, smallest first for Inner, and each
+// is dropped (or merged into) the first kept line it duplicates. The kept lines stay in
+// their original order.
+pub fn suppress_duplicates(
+    lines: Vec<line>,
+    iou_threshold: f32,
+    max_gap: u32,
+    preference: duplicate_preference
+) -> Vec<line> {
+    let mut order: Vec<usize> = (0..lines.len()).collect();
+    // Stable, so equal areas keep their order
+    match preference {
+        duplicate_preference::Inner => order.sort_by_key(|i| lines[*i].bounds.area()),
+        _ => order.sort_by_key(|i| std::cmp::Reverse(lines[*i].bounds.area())),
+    }
+
+    let mut lines: Vec<Option<line>> = lines.into_iter().map(Some).collect();
+    // (original index, line)
+    let mut kept: Vec<(usize, line)> = Vec::new();
+    for i in order {
+        let Some(line) = lines[i].take() else { continue };
+        let duplicate_of = kept.iter()
+            .position(|(_, kept_line)| is_duplicate(&kept_line.bounds, &line.bounds, iou_threshold, max_gap));
+        match duplicate_of {
+            None => kept.push((i, line)),
+            Some(kept_index) if preference == duplicate_preference::Merge => {
+                let (kept_i, kept_line) = kept.remove(kept_index);
+                kept.insert(kept_index, (kept_i.min(i), merge_line_pair(kept_line, line)));
+            },
+            Some(_) => {},
+        }
+    }
+    kept.sort_by_key(|(i, _)| *i);
+    return kept.into_iter().map(|(_, line)| line).collect()
+}
@@ -0,0 +1,365 @@
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+use ab_glyph::{Font, FontRef, InvalidFont, PxScale, ScaleFont};
+use image::RgbImage;
+
+use super::{ocr_error, recognized_word, text_recognizer};
+
+// Templates are rendered from a common sans-serif UI font, in regular and bold
+const TRAINING_FONTS: [&[u8]; 2] = [
+    include_bytes!("../../assets/fonts/DejaVuSans.ttf"),
+    include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf"),
+];
+// Pixel sizes templates are rendered at, covering typical UI text
+const TRAINING_SIZES: [f32; 5] = [11.0, 13.0, 16.0, 20.0, 26.0];
+const TRAINING_CHARS: RangeInclusive<char> = '!'..='~'; // Printable ASCII, except space
+
+const GRID_SIZE: usize = 16; // Glyphs are resampled to a GRID_SIZE x GRID_SIZE grid to be compared
+const GRID_SUBSAMPLES: usize = 4; // Samples per grid cell along each axis
+const INK_THRESHOLD: f32 = 0.5; // Minimum ink for a pixel to be counted as part of a glyph
+const MIN_CONTRAST: f32 = 24.0; // Minimum luma difference between text and its background
+const SPACE_WIDTH_RATIO: f32 = 0.3; // Gaps wider than this fraction of line height separate words
+// How much glyph shape matters relative to the shape of the grid
+const ASPECT_WEIGHT: f32 = 0.5;
+const POSITION_WEIGHT: f32 = 2.0;
+// Added to the distance of punctuation, as UI text is mostly letters and digits
+// and e.g. 'l' and '|' are near identical
+const PUNCTUATION_PENALTY: f32 = 0.08;
+
+// How much each pixel looks like text (1.0) rather than background (0.0)
+pub struct ink_map {
+    pub width: u32,
+    pub height: u32,
+    pub ink: Vec<f32>,
+}
+
+impl ink_map {
+    fn get(&self, x: u32, y: u32) -> f32 {
+        return self.ink[(y * self.width + x) as usize];
+    }
+
+    // Bilinear sample between pixel centres, clamped to the given box
+    fn sample(&self, x: f32, y: f32, glyph_box: &glyph_box) -> f32 {
+        let x = x.clamp(glyph_box.x0 as f32, glyph_box.x1 as f32);
+        let y = y.clamp(glyph_box.y0 as f32, glyph_box.y1 as f32);
+        let x0 = x.floor() as u32;
+        let y0 = y.floor() as u32;
+        let x1 = std::cmp::min(x0 + 1, glyph_box.x1);
+        let y1 = std::cmp::min(y0 + 1, glyph_box.y1);
+        let dx = x - x0 as f32;
+        let dy = y - y0 as f32;
+        let top = self.get(x0, y0) * (1.0 - dx) + self.get(x1, y0) * dx;
+        let bottom = self.get(x0, y1) * (1.0 - dx) + self.get(x1, y1) * dx;
+        return top * (1.0 - dy) + bottom * dy;
+    }
+}
+
+fn get_luma(pixel: &image::Rgb<u8>) -> f32 {
+    return 0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32;
+}
+
+// Treats the colour most common around the border as background, and everything
+// else as ink in proportion to how far it is from it. Works for light and dark text.
+// Returns None if there isn't enough contrast for there to be text.
+pub fn get_ink_map(image: &RgbImage) -> Option<ink_map> {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let mut border_lumas: Vec<f32> = Vec::new();
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+            border_lumas.push(get_luma(pixel));
+        }
+    }
+    border_lumas.sort_by(|a, b| a.total_cmp(b));
+    let background = border_lumas[border_lumas.len() / 2];
+
+    let mut differences: Vec<f32> = image.pixels()
+        .map(|pixel| (get_luma(pixel) - background).abs())
+        .collect();
+    let mut sorted_differences = differences.clone();
+    sorted_differences.sort_by(|a, b| a.total_cmp(b));
+    // Near the top rather than the max, so a few stray pixels don't set the contrast
+    let contrast = sorted_differences[(sorted_differences.len() - 1) * 97 / 100];
+    if contrast < MIN_CONTRAST {
+        return None;
+    }
+    for difference in differences.iter_mut() {
+        *difference = (*difference / contrast).min(1.0);
+    }
+    return Some(ink_map { width, height, ink: differences });
+}
+
+// Inclusive pixel bounds of a glyph within an ink_map
+pub struct glyph_box {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+// Splits a line of text into glyphs at every column without ink
+pub fn get_glyph_boxes(ink: &ink_map) -> Vec<glyph_box> {
+    let mut glyph_boxes: Vec<glyph_box> = Vec::new();
+    let mut x = 0;
+    while x < ink.width {
+        let column_has_ink = |x: u32| (0..ink.height).any(|y| ink.get(x, y) >= INK_THRESHOLD);
+        if !column_has_ink(x) {
+            x += 1;
+            continue
+        }
+        let x0 = x;
+        while x < ink.width && column_has_ink(x) {
+            x += 1;
+        }
+        let x1 = x - 1;
+        let inked_rows: Vec<u32> = (0..ink.height)
+            .filter(|y| (x0..=x1).any(|x| ink.get(x, *y) >= INK_THRESHOLD))
+            .collect();
+        glyph_boxes.push(glyph_box {
+            x0,
+            y0: inked_rows[0],
+            x1,
+            y1: inked_rows[inked_rows.len() - 1],
+        });
+    }
+    return glyph_boxes;
+}
+
+// What a glyph is compared on
+#[derive(Clone)]
+pub struct glyph_features {
+    grid: Vec<f32>, // Ink resampled to GRID_SIZE x GRID_SIZE, zero mean and unit length
+    aspect: f32, // Width / height of the glyph's box
+    top: f32, // Position of the glyph's top and bottom edges within the line, 0.0 = line top
+    bottom: f32,
+}
+
+pub fn get_glyph_features(ink: &ink_map, glyph_box: &glyph_box, top: f32, bottom: f32) -> glyph_features {
+    let box_width = (glyph_box.x1 - glyph_box.x0 + 1) as f32;
+    let box_height = (glyph_box.y1 - glyph_box.y0 + 1) as f32;
+    let samples_per_side = (GRID_SIZE * GRID_SUBSAMPLES) as f32;
+    let mut grid: Vec<f32> = vec![0.0; GRID_SIZE * GRID_SIZE];
+    for grid_y in 0..GRID_SIZE {
+        for grid_x in 0..GRID_SIZE {
+            let mut total = 0.0;
+            for sub_y in 0..GRID_SUBSAMPLES {
+                for sub_x in 0..GRID_SUBSAMPLES {
+                    let u = ((grid_x * GRID_SUBSAMPLES + sub_x) as f32 + 0.5) / samples_per_side;
+                    let v = ((grid_y * GRID_SUBSAMPLES + sub_y) as f32 + 0.5) / samples_per_side;
+                    // -0.5 as pixel values are at their centres
+                    total += ink.sample(
+                        glyph_box.x0 as f32 + u * box_width - 0.5,
+                        glyph_box.y0 as f32 + v * box_height - 0.5,
+                        glyph_box
+                    );
+                }
+            }
+            grid[grid_y * GRID_SIZE + grid_x] = total / (GRID_SUBSAMPLES * GRID_SUBSAMPLES) as f32;
+        }
+    }
+    // Normalising makes the comparison a correlation, independent of stroke weight
+    let mean = grid.iter().sum::<f32>() / grid.len() as f32;
+    for value in grid.iter_mut() {
+        *value -= mean;
+    }
+    let length = grid.iter().map(|value| value * value).sum::<f32>().sqrt();
+    for value in grid.iter_mut() {
+        // Solid glyphs (e.g. '-', '.') have no shape, so are told apart only by aspect and position
+        *value = if length > 1e-3 { *value / length } else { 0.0 };
+    }
+    return glyph_features { grid, aspect: box_width / box_height, top, bottom };
+}
+
+fn get_features_distance(a: &glyph_features, b: &glyph_features) -> f32 {
+    let correlation: f32 = a.grid.iter().zip(&b.grid).map(|(a, b)| a * b).sum();
+    let aspect_difference = (a.aspect / b.aspect).ln();
+    let top_difference = a.top - b.top;
+    let bottom_difference = a.bottom - b.bottom;
+    return (1.0 - correlation)
+        + ASPECT_WEIGHT * aspect_difference * aspect_difference
+        + POSITION_WEIGHT * (top_difference * top_difference + bottom_difference * bottom_difference);
+}
+
+// Glyphs which look the same in most sans-serif fonts are decided by the letters
+// either side, e.g. "He||o" -> "Hello", "c0m" -> "com"
+fn fix_ambiguous_characters(text: &str) -> String {
+    let mut characters: Vec<char> = text.chars().collect();
+    // Fixed in place, so runs like "||" are fixed one after another
+    for i in 0..characters.len() {
+        let previous_lowercase = i > 0 && characters[i - 1].is_ascii_lowercase();
+        let next_lowercase = characters.get(i + 1).is_some_and(|next| next.is_ascii_lowercase());
+        characters[i] = match characters[i] {
+            'I' | '|' | '1' if previous_lowercase => 'l',
+            '0' if previous_lowercase || next_lowercase => 'o',
+            '5' | '$' if previous_lowercase || next_lowercase => 's',
+            character => character,
+        };
+    }
+    return characters.into_iter().collect();
+}
+
+pub struct glyph_template {
+    pub character: char,
+    pub features: glyph_features,
+}
+
+// Renders every training character of a font at the given size into templates
+fn render_templates(font: &FontRef, size: f32) -> Vec<glyph_template> {
+    let scaled_font = font.as_scaled(PxScale::from(size));
+    // Line extent is from the highest ascender to the lowest descender of the alphabet,
+    // matching what a line of real text would span
+    let mut line_top = f32::MAX;
+    let mut line_bottom = f32::MIN;
+    for character in ('A'..='Z').chain('a'..='z') {
+        if let Some(outline) = scaled_font.outline_glyph(scaled_font.scaled_glyph(character)) {
+            let bounds = outline.px_bounds();
+            line_top = line_top.min(bounds.min.y);
+            line_bottom = line_bottom.max(bounds.max.y);
+        }
+    }
+    let line_height = line_bottom - line_top;
+
+    let mut templates: Vec<glyph_template> = Vec::new();
+    for character in TRAINING_CHARS {
+        let Some(outline) = scaled_font.outline_glyph(scaled_font.scaled_glyph(character)) else {
+            continue
+        };
+        let bounds = outline.px_bounds();
+        let width = bounds.width() as u32;
+        let height = bounds.height() as u32;
+        let mut ink = ink_map { width, height, ink: vec![0.0; (width * height) as usize] };
+        outline.draw(|x, y, coverage| {
+            if x < width && y < height {
+                ink.ink[(y * width + x) as usize] = coverage;
+            }
+        });
+        // Templates use the same thresholded box as glyphs found by get_glyph_boxes
+        let Some(glyph_box) = get_glyph_boxes(&ink).into_iter().reduce(|a, b| glyph_box {
+            x0: a.x0.min(b.x0),
+            y0: a.y0.min(b.y0),
+            x1: a.x1.max(b.x1),
+            y1: a.y1.max(b.y1),
+        }) else {
+            continue
+        };
+        let top = (bounds.min.y + glyph_box.y0 as f32 - line_top) / line_height;
+        let bottom = (bounds.min.y + glyph_box.y1 as f32 + 1.0 - line_top) / line_height;
+        templates.push(glyph_template {
+            character,
+            features: get_glyph_features(&ink, &glyph_box, top, bottom),
+        });
+    }
+    return templates;
+}
+
+fn get_font_templates(fonts: &[&[u8]], sizes: &[f32]) -> Result<Vec<glyph_template>, InvalidFont> {
+    let mut templates: Vec<glyph_template> = Vec::new();
+    for font_data in fonts {
+        let font = FontRef::try_from_slice(font_data)?;
+        for size in sizes {
+            templates.extend(render_templates(&font, *size));
+        }
+    }
+    return Ok(templates)
+}
+
+// Offline recogniser which matches each glyph against templates rendered from fonts.
+// Best on single lines of unstyled UI text, in fonts similar to those it was trained on.
+pub struct glyph_recognizer {
+    // Filled on first use by the default recogniser, as rendering every template is slow
+    templates: OnceLock<Vec<glyph_template>>,
+}
+
+impl glyph_recognizer {
+    pub fn new(templates: Vec<glyph_template>) -> glyph_recognizer {
+        return glyph_recognizer { templates: OnceLock::from(templates) }
+    }
+
+    // Creates templates from every font (TTF/OTF data) rendered at every size
+    pub fn train(fonts: &[&[u8]], sizes: &[f32]) -> Result<glyph_recognizer, InvalidFont> {
+        return Ok(glyph_recognizer::new(get_font_templates(fonts, sizes)?))
+    }
+
+    fn get_templates(&self) -> &[glyph_template] {
+        return self.templates.get_or_init(|| {
+            get_font_templates(&TRAINING_FONTS, &TRAINING_SIZES).expect("bundled fonts are valid")
+        })
+    }
+
+    // Returns the best matching character and how far it was from the glyph
+    fn get_closest_character(&self, features: &glyph_features) -> (char, f32) {
+        let mut closest = ' ';
+        let mut closest_distance = f32::MAX;
+        for template in self.get_templates() {
+            let mut distance = get_features_distance(features, &template.features);
+            if !template.character.is_ascii_alphanumeric() {
+                distance += PUNCTUATION_PENALTY;
+            }
+            if distance < closest_distance {
+                closest = template.character;
+                closest_distance = distance;
+            }
+        }
+        return (closest, closest_distance);
+    }
+}
+
+// Trained on the bundled fonts at TRAINING_SIZES when it first reads something
+impl Default for glyph_recognizer {
+    fn default() -> glyph_recognizer {
+        return glyph_recognizer { templates: OnceLock::new() }
+    }
+}
+
+impl text_recognizer for glyph_recognizer {
+    fn recognize_words(&self, image: &RgbImage) -> Result<Vec<recognized_word>, ocr_error> {
+        let Some(ink) = get_ink_map(image) else {
+            return Ok(Vec::new())
+        };
+        let glyph_boxes = get_glyph_boxes(&ink);
+        let Some(line_top) = glyph_boxes.iter().map(|glyph_box| glyph_box.y0).min() else {
+            return Ok(Vec::new())
+        };
+        let line_bottom = glyph_boxes.iter().map(|glyph_box| glyph_box.y1).max().unwrap_or(line_top);
+        let line_height = (line_bottom - line_top + 1) as f32;
+
+        // Glyphs are grouped into words, split wherever the gap between them is wide enough
+        let mut word_glyphs: Vec<Vec<(&glyph_box, char, f32)>> = Vec::new();
+        for (i, glyph_box) in glyph_boxes.iter().enumerate() {
+            let is_new_word = i == 0
+                || (glyph_box.x0 - glyph_boxes[i - 1].x1 - 1) as f32 > SPACE_WIDTH_RATIO * line_height;
+            if is_new_word {
+                word_glyphs.push(Vec::new());
+            }
+            let top = (glyph_box.y0 - line_top) as f32 / line_height;
+            let bottom = (glyph_box.y1 + 1 - line_top) as f32 / line_height;
+            let features = get_glyph_features(&ink, glyph_box, top, bottom);
+            let (character, distance) = self.get_closest_character(&features);
+            if let Some(glyphs) = word_glyphs.last_mut() {
+                glyphs.push((glyph_box, character, distance));
+            }
+        }
+
+        let mut words: Vec<recognized_word> = Vec::new();
+        for glyphs in word_glyphs {
+            let text: String = glyphs.iter().map(|glyph| glyph.1).collect();
+            let x0 = glyphs.iter().map(|glyph| glyph.0.x0).min().unwrap_or(0);
+            let y0 = glyphs.iter().map(|glyph| glyph.0.y0).min().unwrap_or(0);
+            let x1 = glyphs.iter().map(|glyph| glyph.0.x1).max().unwrap_or(0);
+            let y1 = glyphs.iter().map(|glyph| glyph.0.y1).max().unwrap_or(0);
+            // A perfect match has a distance of 0, an unrelated glyph around 1
+            let mean_distance = glyphs.iter().map(|glyph| glyph.2).sum::<f32>() / glyphs.len() as f32;
+            words.push(recognized_word {
+                text: fix_ambiguous_characters(&text),
+                x: x0,
+                y: y0,
+                width: x1 - x0 + 1,
+                height: y1 - y0 + 1,
+                confidence: (1.0 - mean_distance).clamp(0.0, 1.0),
+            });
+        }
+        return Ok(words)
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+use image::RgbImage;
+
+pub mod glyph;
+#[cfg(feature = "tesseract")]
+pub mod tesseract;
+
+pub use glyph::glyph_recognizer;
+#[cfg(feature = "tesseract")]
+pub use tesseract::tesseract_recognizer;
+
+// A single word read from an image, box is relative to the image it was read from
+#[derive(Clone, Debug)]
+pub struct recognized_word {
+    pub text: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub confidence: f32, // 0.0 - 1.0, how sure the engine is the text is right
+}
+
+#[derive(Debug)]
+pub enum ocr_error {
+    EngineMissing(String), // The engine isn't installed or couldn't be started
+    Failed(String), // The engine ran but couldn't read the image
+}
+
+impl fmt::Display for ocr_error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            ocr_error::EngineMissing(message) => write!(f, "OCR engine unavailable: {}", message),
+            ocr_error::Failed(message) => write!(f, "OCR failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ocr_error {}
+
+// Anything which can read the text in a crop of a screenshot, so engines can be swapped out
+pub trait text_recognizer {
+    // Returns every word in the image in reading order, empty if none could be read
+    fn recognize_words(&self, image: &RgbImage) -> Result<Vec<recognized_word>, ocr_error>;
+
+    // Returns the text in the image, or "" if none could be read
+    fn recognize(&self, image: &RgbImage) -> Result<String, ocr_error> {
+        let words = self.recognize_words(image)?;
+        let texts: Vec<&str> = words.iter().map(|word| word.text.as_str()).collect();
+        return Ok(texts.join(" "))
+    }
+}
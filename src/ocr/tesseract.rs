@@ -0,0 +1,139 @@
+use std::io::{Cursor, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use image::{imageops, ImageFormat, RgbImage};
+
+use super::{ocr_error, recognized_word, text_recognizer};
+
+// Tesseract struggles with small text, so crops are scaled up to at least this height
+const MIN_CROP_HEIGHT: u32 = 40;
+const PAGE_SEGMENTATION_MODE: &str = "7"; // Treat the image as a single line of text
+const WORD_LEVEL: &str = "5"; // Level of word rows in Tesseract's TSV output
+
+// Reads text by running a locally installed `tesseract` binary on each crop
+pub struct tesseract_recognizer {
+    binary: PathBuf,
+    language: String,
+}
+
+impl tesseract_recognizer {
+    // Uses `tesseract` from PATH, failing if it can't be run
+    pub fn new() -> Result<tesseract_recognizer, ocr_error> {
+        return tesseract_recognizer::with_binary("tesseract")
+    }
+
+    pub fn with_binary(binary: impl Into<PathBuf>) -> Result<tesseract_recognizer, ocr_error> {
+        let recognizer = tesseract_recognizer { binary: binary.into(), language: "eng".to_string() };
+        // Checked now, so a missing install is reported once rather than for every crop
+        let output = recognizer.command().arg("--version").output()
+            .map_err(|error| recognizer.get_spawn_error(error))?;
+        if !output.status.success() {
+            return Err(ocr_error::EngineMissing(format!(
+                "{} --version exited with {}", recognizer.binary.display(), output.status
+            )));
+        }
+        return Ok(recognizer)
+    }
+
+    // Tesseract language code(s), e.g. "eng" or "eng+fra"
+    pub fn with_language(mut self, language: &str) -> tesseract_recognizer {
+        self.language = language.to_string();
+        return self
+    }
+
+    fn command(&self) -> Command {
+        return Command::new(&self.binary)
+    }
+
+    fn get_spawn_error(&self, error: std::io::Error) -> ocr_error {
+        if error.kind() == ErrorKind::NotFound {
+            return ocr_error::EngineMissing(format!(
+                "{} not found, is Tesseract installed and on PATH?", self.binary.display()
+            ));
+        }
+        return ocr_error::EngineMissing(format!("couldn't run {}: {}", self.binary.display(), error));
+    }
+}
+
+// Parses the word rows of Tesseract's TSV output, with boxes divided by scale
+// to be relative to the original image
+pub fn parse_tsv(tsv: &str, scale: f32) -> Result<Vec<recognized_word>, ocr_error> {
+    let mut lines = tsv.lines();
+    let header: Vec<&str> = lines.next().unwrap_or("").split('\t').collect();
+    let column = |name: &str| header.iter().position(|column| *column == name)
+        .ok_or_else(|| ocr_error::Failed(format!("TSV output has no {} column", name)));
+    let level_column = column("level")?;
+    let left_column = column("left")?;
+    let top_column = column("top")?;
+    let width_column = column("width")?;
+    let height_column = column("height")?;
+    let conf_column = column("conf")?;
+    let text_column = column("text")?;
+    // Rows without every column used are skipped, wherever the columns are
+    let last_column = [level_column, left_column, top_column, width_column, height_column, conf_column, text_column]
+        .into_iter()
+        .max()
+        .unwrap_or(0);
+
+    let mut words: Vec<recognized_word> = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() <= last_column || fields[level_column] != WORD_LEVEL {
+            continue
+        }
+        let text = fields[text_column].trim();
+        let confidence: f32 = fields[conf_column].trim().parse().unwrap_or(-1.0);
+        // Empty words and negative confidences are layout rows, not text
+        if text.is_empty() || confidence < 0.0 {
+            continue
+        }
+        let get_number = |column: usize| -> Result<u32, ocr_error> {
+            let value: u32 = fields[column].trim().parse()
+                .map_err(|_| ocr_error::Failed(format!("bad number in TSV row: {}", line)))?;
+            return Ok((value as f32 / scale).round() as u32)
+        };
+        words.push(recognized_word {
+            text: text.to_string(),
+            x: get_number(left_column)?,
+            y: get_number(top_column)?,
+            width: get_number(width_column)?,
+            height: get_number(height_column)?,
+            confidence: confidence / 100.0,
+        });
+    }
+    return Ok(words)
+}
+
+impl text_recognizer for tesseract_recognizer {
+    fn recognize_words(&self, image: &RgbImage) -> Result<Vec<recognized_word>, ocr_error> {
+        if image.width() == 0 || image.height() == 0 {
+            return Ok(Vec::new())
+        }
+        let scale = std::cmp::max(1, MIN_CROP_HEIGHT.div_ceil(image.height()));
+        let scaled_image = imageops::resize(
+            image,
+            image.width() * scale,
+            image.height() * scale,
+            imageops::FilterType::CatmullRom
+        );
+        let mut png: Vec<u8> = Vec::new();
+        scaled_image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|error| ocr_error::Failed(error.to_string()))?;
+
+        let mut child = self.command()
+            .args(["stdin", "stdout", "-l", &self.language, "--psm", PAGE_SEGMENTATION_MODE, "tsv"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| self.get_spawn_error(error))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&png).map_err(|error| ocr_error::Failed(error.to_string()))?;
+        }
+        let output = child.wait_with_output().map_err(|error| ocr_error::Failed(error.to_string()))?;
+        if !output.status.success() {
+            return Err(ocr_error::Failed(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+        return parse_tsv(&String::from_utf8_lossy(&output.stdout), scale as f32)
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::bounds::rect;
+use crate::classify::element_kind;
+use crate::frequency::layout;
+use crate::hierarchy::describe_element;
+use crate::geometry::rotated_rect;
+use crate::element;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct word_output {
+    pub text: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub confidence: f32,
+}
+
+// Machine readable version of a text_line
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct text_output {
+    pub stroke_color: [u8; 3],
+    pub text: String,
+    pub words: Vec<word_output>,
+}
+
+// Machine readable version of an element, with the stats needed to describe it
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct element_output {
+    pub bounds: rect,
+    pub top_left: (u32, u32),
+    pub top_right: (u32, u32),
+    pub bottom_left: (u32, u32),
+    pub bottom_right: (u32, u32),
+    pub hull: Option<Vec<(u32, u32)>>, // Only present if rotated bounds were asked for
+    pub rotated_bounds: Option<rotated_rect>,
+    pub pixel_count: usize,
+    pub area: u32,
+    pub fill_ratio: f32, // See line::get_activation
+    pub dominant_color: [u8; 3],
+    pub text: Option<text_output>, // Only present if the line was found to be text
+    pub kind: element_kind,
+    pub confidence: f32,
+    pub id: usize, // Index in elements, which parent and children refer to
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub label: Option<String>,
+    pub description: String, // See describe_element
+}
+
+// All elements found in a single image
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct image_output {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub elements: Vec<element_output>,
+    pub layouts: Option<Vec<layout>>, // Only present if layouts were asked for, see get_layouts
+}
+
+pub fn get_element_output(elements: &[element], id: usize) -> element_output {
+    let element = &elements[id];
+    let line = &element.line;
+    let text = element.text.as_ref().map(|text| text_output {
+        stroke_color: text.stroke_color.0,
+        text: text.text.clone(),
+        words: text.words.iter().map(|word| word_output {
+            text: word.text.clone(),
+            x: word.x,
+            y: word.y,
+            width: word.width,
+            height: word.height,
+            confidence: word.confidence,
+        }).collect(),
+    });
+    return element_output {
+        bounds: line.bounds,
+        top_left: line.bounds.top_left(),
+        top_right: line.bounds.top_right(),
+        bottom_left: line.bounds.bottom_left(),
+        bottom_right: line.bounds.bottom_right(),
+        hull: line.hull.clone(),
+        rotated_bounds: line.rotated_bounds,
+        pixel_count: line.pixels.len(),
+        area: line.bounds.area(),
+        fill_ratio: line.get_activation(),
+        dominant_color: element.dominant_color.0,
+        text,
+        kind: element.kind,
+        confidence: element.confidence,
+        id,
+        parent: element.parent,
+        children: element.children.clone(),
+        label: element.label.clone(),
+        description: describe_element(elements, id),
+    }
+}
+
+pub fn get_elements_output(elements: &[element]) -> Vec<element_output> {
+    return (0..elements.len()).map(|id| get_element_output(elements, id)).collect();
+}
@@ -0,0 +1,491 @@
+use std::fmt;
+use std::path::Path;
+use image::{ImageBuffer, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use crate::activation::{activation_map, get_activation_stats, IMMEDIATE_NEIGHBOUR_WEIGHT, MAX_ACTIVATION};
+use crate::classify::{classify, element_kind, get_elements_features};
+use crate::color::color_difference;
+use crate::edges::{
+    edge_filter, edge_operator, edge_settings, BLUR_SIGMA, CANNY_HIGH_THRESHOLD, CANNY_LOW_THRESHOLD
+};
+use crate::grouping::{add_paragraphs, merge_text_lines};
+use crate::hierarchy::build_hierarchy;
+use crate::lines::{
+    connectivity, get_lines_stats, get_lines_with, line, sanitise_lines,
+    ACTIVATION_THRESHOLD, AREA_THRESHOLD, HYSTERESIS_RATIO, LARGER_WIDTH_THRESHOLD
+};
+use crate::nms::{duplicate_preference, suppress_duplicates, DUPLICATE_IOU_THRESHOLD, DUPLICATE_MAX_GAP};
+use crate::ocr::{glyph_recognizer, ocr_error, recognized_word, text_recognizer};
+use crate::profile::config_profile;
+use crate::pyramid::{
+    get_pyramid, merge_pyramid_lines, scale_line_pixels, PYRAMID_IOU_THRESHOLD, PYRAMID_LEVELS, PYRAMID_SCALE
+};
+use crate::threshold::{
+    get_local_thresholds, get_mean_std_threshold, get_otsu_threshold, get_percentile_threshold, threshold_mode,
+    ADAPTIVE_OFFSET, ADAPTIVE_RADIUS, AUTOMATIC_GROW_RATIO, THRESHOLD_PERCENTILE, THRESHOLD_STD_COUNT
+};
+use crate::text::{
+    get_line_colors, get_most_common_color, get_text_lines, text_line, transcribe_text_lines,
+    DIFFERENCE_COLOR_THRESH, TEXT_HIGH_FREQUENCY_SHARE
+};
+
+pub const LINE_THRESHOLD: f32 = 15.0;
+
+// Every tunable value in the pipeline, defaults are the hand-tuned constants.
+// Saved as TOML or JSON, where missing fields take their profile's value, see detector_config::load
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct detector_config {
+    pub line_threshold: f32, // Minimum activation for a pixel to be part of a line
+    pub threshold_mode: threshold_mode, // How the threshold is chosen, Fixed uses line_threshold
+    pub threshold_percentile: f32, // Percentile only
+    pub threshold_std_count: f32, // MeanStd only
+    pub adaptive_radius: u32, // Adaptive only, see get_local_thresholds
+    pub adaptive_offset: f32,
+    // Lines grow through pixels above this fraction of the threshold, see get_hysteresis_lines.
+    // 1.0 uses the one threshold for both. Otsu, Percentile and MeanStd use at most
+    // AUTOMATIC_GROW_RATIO.
+    pub hysteresis_ratio: f32,
+    pub connectivity: connectivity, // Which neighbouring pixels join lines
+    pub normalise_activation: bool, // See activation_map::normalise
+    pub edge_operator: edge_operator, // How activation is found, see edge_filter
+    pub immediate_neighbour_weight: f32, // See get_pixel_activation
+    // Used by the brush and get_line_colors, see color_difference
+    pub color_difference: color_difference,
+    pub difference_color_threshold: f32, // See DIFFERENCE_COLOR_THRESH
+    pub blur_sigma: f32, // LaplacianOfGaussian and Canny only
+    pub canny_low_threshold: f32,
+    pub canny_high_threshold: f32,
+    pub area_threshold: u32, // See sanitise_lines
+    pub larger_width_threshold: u32,
+    pub activation_threshold: f32,
+    // Run the above over smaller copies of the image too, see get_pyramid. 1 turns this off
+    pub pyramid_levels: u32,
+    pub pyramid_scale: f32,
+    pub pyramid_iou_threshold: f32, // See merge_pyramid_lines
+    // Merge or drop overlapping lines, e.g. a focus ring and border, see suppress_duplicates
+    pub suppress_duplicates: bool,
+    pub duplicate_iou_threshold: f32, // See is_duplicate
+    pub duplicate_max_gap: u32,
+    pub duplicate_preference: duplicate_preference,
+    pub text_high_frequency_share: f32, // See get_text_lines, 0.0 turns this off
+    pub rotated_bounds: bool, // Find each line's convex hull and rotated bounds, see line::add_rotated_bounds
+}
+
+impl Default for detector_config {
+    fn default() -> detector_config {
+        return detector_config {
+            line_threshold: LINE_THRESHOLD,
+            threshold_mode: threshold_mode::Fixed,
+            threshold_percentile: THRESHOLD_PERCENTILE,
+            threshold_std_count: THRESHOLD_STD_COUNT,
+            adaptive_radius: ADAPTIVE_RADIUS,
+            adaptive_offset: ADAPTIVE_OFFSET,
+            hysteresis_ratio: HYSTERESIS_RATIO,
+            connectivity: connectivity::Eight,
+            normalise_activation: false,
+            edge_operator: edge_operator::Brush,
+            immediate_neighbour_weight: IMMEDIATE_NEIGHBOUR_WEIGHT,
+            color_difference: color_difference::Rgb,
+            difference_color_threshold: DIFFERENCE_COLOR_THRESH,
+            blur_sigma: BLUR_SIGMA,
+            canny_low_threshold: CANNY_LOW_THRESHOLD,
+            canny_high_threshold: CANNY_HIGH_THRESHOLD,
+            area_threshold: AREA_THRESHOLD,
+            larger_width_threshold: LARGER_WIDTH_THRESHOLD,
+            activation_threshold: ACTIVATION_THRESHOLD,
+            pyramid_levels: PYRAMID_LEVELS,
+            pyramid_scale: PYRAMID_SCALE,
+            pyramid_iou_threshold: PYRAMID_IOU_THRESHOLD,
+            suppress_duplicates: false,
+            duplicate_iou_threshold: DUPLICATE_IOU_THRESHOLD,
+            duplicate_max_gap: DUPLICATE_MAX_GAP,
+            duplicate_preference: duplicate_preference::Outer,
+            text_high_frequency_share: TEXT_HIGH_FREQUENCY_SHARE,
+            rotated_bounds: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum config_error {
+    Read(String), // The file couldn't be read or written
+    Invalid(String), // The file isn't a config
+    OutOfRange(String), // A setting has a value the pipeline can't use
+}
+
+impl fmt::Display for config_error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            config_error::Read(message) => write!(f, "couldn't access config: {}", message),
+            config_error::Invalid(message) => write!(f, "invalid config: {}", message),
+            config_error::OutOfRange(message) => write!(f, "config setting out of range: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for config_error {}
+
+// Files ending .toml are TOML, anything else JSON
+fn is_toml(path: &Path) -> bool {
+    return path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("toml"))
+}
+
+// Err naming the setting unless min <= value <= max
+pub fn check_range<T: PartialOrd + fmt::Display>(name: &str, value: T, min: T, max: T) -> Result<(), config_error> {
+    // Written so NaN fails too
+    if value >= min && value <= max {
+        return Ok(())
+    }
+    return Err(config_error::OutOfRange(format!("{} is {}, expected {} to {}", name, value, min, max)))
+}
+
+impl detector_config {
+    // Reads a config written by save (or by hand), as TOML or JSON by extension. Fields left
+    // out keep their values in the profile named by the optional "profile" key, the default
+    // if there isn't one, see config_profile. The result is validated.
+    pub fn load(path: &Path) -> Result<detector_config, config_error> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| config_error::Read(format!("{}: {}", path.display(), error)))?;
+        let parsed: Result<serde_json::Value, String> = if is_toml(path) {
+            toml::from_str(&contents).map_err(|error| error.to_string())
+        } else {
+            serde_json::from_str(&contents).map_err(|error| error.to_string())
+        };
+        let mut value = parsed.map_err(|error| config_error::Invalid(format!("{}: {}", path.display(), error)))?;
+        let config = detector_config::from_value(&mut value)
+            .map_err(|error| config_error::Invalid(format!("{}: {}", path.display(), error)))?;
+        return config.validate()
+            .map(|_| config)
+            .map_err(|error| match error {
+                config_error::OutOfRange(message) => config_error::OutOfRange(format!("{}: {}", path.display(), message)),
+                error => error,
+            })
+    }
+
+    // Settings in value on top of its profile's
+    fn from_value(value: &mut serde_json::Value) -> Result<detector_config, String> {
+        let serde_json::Value::Object(settings) = value else {
+            return Err("expected a table of settings".to_string())
+        };
+        let profile = match settings.remove("profile") {
+            Some(serde_json::Value::String(name)) => name.parse::<config_profile>()?,
+            Some(other) => return Err(format!("profile should be a name, not {}", other)),
+            None => config_profile::Default,
+        };
+        let mut config = serde_json::to_value(profile.get_config()).map_err(|error| error.to_string())?;
+        for (name, setting) in std::mem::take(settings) {
+            // Unknown names are kept so deserialising reports them
+            config[name] = setting;
+        }
+        return serde_json::from_value(config).map_err(|error| error.to_string())
+    }
+
+    // Writes every setting, as TOML or JSON by extension
+    pub fn save(&self, path: &Path) -> Result<(), config_error> {
+        let contents = if is_toml(path) {
+            toml::to_string_pretty(self).map_err(|error| config_error::Invalid(error.to_string()))?
+        } else {
+            serde_json::to_string_pretty(self).map_err(|error| config_error::Invalid(error.to_string()))?
+        };
+        return std::fs::write(path, contents)
+            .map_err(|error| config_error::Read(format!("{}: {}", path.display(), error)))
+    }
+
+    // Err for the first setting outside what its stage can use, e.g. a pyramid_scale of 1.0
+    // would never shrink the image
+    pub fn validate(&self) -> Result<(), config_error> {
+        check_range("line_threshold", self.line_threshold, 0.0, MAX_ACTIVATION)?;
+        check_range("threshold_percentile", self.threshold_percentile, 0.0, 100.0)?;
+        check_range("threshold_std_count", self.threshold_std_count, -10.0, 10.0)?;
+        check_range("adaptive_radius", self.adaptive_radius, 1, 512)?;
+        check_range("adaptive_offset", self.adaptive_offset, -MAX_ACTIVATION, MAX_ACTIVATION)?;
+        check_range("hysteresis_ratio", self.hysteresis_ratio, 0.01, 1.0)?;
+        check_range("immediate_neighbour_weight", self.immediate_neighbour_weight, 0.0, 1.0)?;
+        // Any higher and every pair of colours would count as the same
+        let max_difference = self.color_difference.get_max_difference();
+        check_range("difference_color_threshold", self.difference_color_threshold, 0.0, max_difference)?;
+        check_range("blur_sigma", self.blur_sigma, 0.0, 32.0)?;
+        // Above 0.0, or hysteresis would follow every pixel non-maximum suppression cleared
+        check_range("canny_low_threshold", self.canny_low_threshold, 0.01, MAX_ACTIVATION)?;
+        check_range("canny_high_threshold", self.canny_high_threshold, self.canny_low_threshold, MAX_ACTIVATION)?;
+        check_range("area_threshold", self.area_threshold, 1, u32::MAX)?;
+        check_range("larger_width_threshold", self.larger_width_threshold, 1, u32::MAX)?;
+        check_range("activation_threshold", self.activation_threshold, 0.0, 1.0)?;
+        check_range("pyramid_levels", self.pyramid_levels, 1, 8)?;
+        check_range("pyramid_scale", self.pyramid_scale, 0.1, 0.9)?;
+        check_range("pyramid_iou_threshold", self.pyramid_iou_threshold, 0.01, 1.0)?;
+        check_range("duplicate_iou_threshold", self.duplicate_iou_threshold, 0.01, 1.0)?;
+        check_range("duplicate_max_gap", self.duplicate_max_gap, 0, 64)?;
+        check_range("text_high_frequency_share", self.text_high_frequency_share, 0.0, 1.0)?;
+        return Ok(())
+    }
+}
+
+// Text found in an element, owned version of text_line
+#[derive(Clone, Debug)]
+pub struct element_text {
+    pub stroke_color: image::Rgb<u8>,
+    pub text: String,
+    pub words: Vec<recognized_word>,
+}
+
+// A detected UI element: a sanitised line plus what is known about it
+pub struct element {
+    pub line: line,
+    pub dominant_color: image::Rgb<u8>,
+    pub text: Option<element_text>, // Only present if the line was found to be text
+    pub kind: element_kind,
+    pub confidence: f32, // How sure the classifier is of kind, 0.0 - 1.0
+    // See build_hierarchy, indices are into the elements returned alongside this one
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub label: Option<String>, // Text of the element's text children, e.g. a button's caption
+}
+
+pub struct detector {
+    config: detector_config,
+    recognizer: Option<Box<dyn text_recognizer>>, // Reads text_lines, if None their text is left empty
+}
+
+impl detector {
+    // Uses the built-in glyph_recognizer for text, see with_recognizer to use another.
+    // It is trained the first time it reads text, so that detect is slow.
+    pub fn new(config: detector_config) -> detector {
+        return detector { config, recognizer: Some(Box::new(glyph_recognizer::default())) }
+    }
+
+    // None leaves text empty, e.g. when only boxes are needed
+    pub fn with_recognizer(mut self, recognizer: Option<Box<dyn text_recognizer>>) -> detector {
+        self.recognizer = recognizer;
+        return self
+    }
+
+    pub fn config(&self) -> &detector_config {
+        return &self.config
+    }
+
+    // Runs the full pipeline over an image, returning every element found.
+    // If the recognizer fails text is left empty, see try_detect
+    pub fn detect(&self, buffer: &RgbImage) -> Vec<element> {
+        return self.run(buffer).0
+    }
+
+    // Same as detect, but fails if the recognizer does
+    pub fn try_detect(&self, buffer: &RgbImage) -> Result<Vec<element>, ocr_error> {
+        return match self.run(buffer) {
+            (_, Some(error)) => Err(error),
+            (elements, None) => Ok(elements),
+        }
+    }
+
+    // Thresholds the activation map as the config's threshold mode and hysteresis ratio say
+    fn get_lines(&self, activation_map: &activation_map) -> Vec<Vec<(u32, u32)>> {
+        let ratio = self.config.hysteresis_ratio;
+        let connectivity = self.config.connectivity;
+        let threshold = match self.config.threshold_mode {
+            threshold_mode::Fixed => self.config.line_threshold,
+            threshold_mode::Otsu => get_otsu_threshold(&get_activation_stats(activation_map).histogram),
+            threshold_mode::Percentile => get_percentile_threshold(activation_map, self.config.threshold_percentile),
+            threshold_mode::MeanStd => {
+                get_mean_std_threshold(&get_activation_stats(activation_map), self.config.threshold_std_count)
+            },
+            threshold_mode::Adaptive => {
+                let thresholds = get_local_thresholds(
+                    activation_map,
+                    self.config.adaptive_radius,
+                    self.config.adaptive_offset
+                );
+                return get_lines_with(
+                    activation_map,
+                    |x, y| thresholds.get(x, y),
+                    |x, y| thresholds.get(x, y) * ratio,
+                    connectivity
+                )
+            },
+        };
+        // Thresholds found from the histogram only seed lines, see AUTOMATIC_GROW_RATIO
+        let ratio = match self.config.threshold_mode {
+            threshold_mode::Fixed => ratio,
+            _ => ratio.min(AUTOMATIC_GROW_RATIO),
+        };
+        return get_lines_with(activation_map, |_, _| threshold, |_, _| threshold * ratio, connectivity)
+    }
+
+    // Lines in one image, from activation through to sanitise_lines
+    fn get_sanitised_lines(&self, buffer: &RgbImage) -> Vec<line> {
+        let settings = edge_settings {
+            immediate_neighbour_weight: self.config.immediate_neighbour_weight,
+            color_difference: self.config.color_difference,
+            blur_sigma: self.config.blur_sigma,
+            canny_low_threshold: self.config.canny_low_threshold,
+            canny_high_threshold: self.config.canny_high_threshold,
+        };
+        let mut activation_map = edge_filter(buffer, self.config.edge_operator, &settings);
+        if self.config.normalise_activation {
+            activation_map.normalise();
+        }
+        let lines = self.get_lines(&activation_map);
+
+        return self.sanitise(get_lines_stats(lines))
+    }
+
+    fn sanitise(&self, lines: Vec<line>) -> Vec<line> {
+        return sanitise_lines(
+            lines,
+            self.config.area_threshold,
+            self.config.larger_width_threshold,
+            self.config.activation_threshold
+        )
+    }
+
+    // get_sanitised_lines at every level of the pyramid, merged in the original's coordinates
+    fn get_pyramid_lines(&self, buffer: &RgbImage) -> Vec<line> {
+        let pyramid = get_pyramid(buffer, self.config.pyramid_levels, self.config.pyramid_scale);
+        if pyramid.len() == 1 {
+            return self.get_sanitised_lines(buffer)
+        }
+        let original_size = buffer.dimensions();
+        let mut levels: Vec<Vec<line>> = Vec::new();
+        for (i, level) in pyramid.iter().enumerate() {
+            let lines = self.get_sanitised_lines(level);
+            // The first level is the original
+            if i == 0 {
+                levels.push(lines);
+                continue
+            }
+            let scaled_lines: Vec<Vec<(u32, u32)>> = lines.iter()
+                .map(|line| scale_line_pixels(&line.pixels, level.dimensions(), original_size))
+                .collect();
+            levels.push(get_lines_stats(scaled_lines));
+        }
+        // Coarse levels were sanitised in their own pixels, so check everything again in the original's
+        return self.sanitise(merge_pyramid_lines(levels, self.config.pyramid_iou_threshold))
+    }
+
+    fn run(&self, buffer: &RgbImage) -> (Vec<element>, Option<ocr_error>) {
+        let mut lines_stats = self.get_pyramid_lines(buffer);
+        if self.config.suppress_duplicates {
+            lines_stats = suppress_duplicates(
+                lines_stats,
+                self.config.duplicate_iou_threshold,
+                self.config.duplicate_max_gap,
+                self.config.duplicate_preference
+            );
+        }
+
+        // Letters and words are merged into phrases before being read
+        let stroke_colors = get_stroke_colors(
+            &lines_stats,
+            buffer,
+            self.config.color_difference,
+            self.config.difference_color_threshold,
+            self.config.text_high_frequency_share
+        );
+        let (lines_stats, stroke_colors) = merge_text_lines(
+            lines_stats,
+            stroke_colors,
+            self.config.color_difference,
+            self.config.difference_color_threshold
+        );
+        let (texts, error) = get_element_texts(&lines_stats, &stroke_colors, buffer, self.recognizer.as_deref());
+
+        let color_freqs: Vec<_> = lines_stats.iter()
+            .map(|line| {
+                get_line_colors(line, buffer, self.config.color_difference, self.config.difference_color_threshold)
+            })
+            .collect();
+        let color_counts: Vec<usize> = color_freqs.iter().map(|freqs| freqs.len()).collect();
+        let is_text: Vec<bool> = texts.iter().map(|text| text.is_some()).collect();
+        let features = get_elements_features(&lines_stats, &color_counts, &is_text);
+
+        let mut elements: Vec<element> = Vec::new();
+        for (((line, text), freqs), features) in lines_stats.into_iter().zip(texts).zip(&color_freqs).zip(&features) {
+            let dominant_color = get_most_common_color(freqs);
+            let (kind, confidence) = classify(features);
+            elements.push(element {
+                line,
+                dominant_color,
+                text,
+                kind,
+                confidence,
+                parent: None,
+                children: Vec::new(),
+                label: None,
+            });
+        }
+        add_paragraphs(&mut elements, self.config.color_difference, self.config.difference_color_threshold);
+        build_hierarchy(&mut elements);
+        if self.config.rotated_bounds {
+            for element in elements.iter_mut() {
+                element.line.add_rotated_bounds();
+            }
+        }
+        return (elements, error);
+    }
+}
+
+impl Default for detector {
+    fn default() -> detector {
+        return detector::new(detector_config::default())
+    }
+}
+
+// Finds the stroke colour of each line which is text (None if it isn't), in the same order as lines
+fn get_stroke_colors(
+    lines: &[line],
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    color_difference: color_difference,
+    color_threshold: f32,
+    min_high_frequency_share: f32
+) -> Vec<Option<image::Rgb<u8>>> {
+    let text_lines = get_text_lines(lines, buffer, color_difference, color_threshold, min_high_frequency_share);
+    let mut stroke_colors: Vec<Option<image::Rgb<u8>>> = Vec::new();
+    for line in lines {
+        // text_lines borrow from lines, so the same line is the same address
+        let stroke_color = text_lines.iter()
+            .find(|text_line| std::ptr::eq(text_line.line, line))
+            .map(|text_line| text_line.stroke_color);
+        stroke_colors.push(stroke_color);
+    }
+    return stroke_colors;
+}
+
+// Reads the text of each line with a stroke colour, in the same order as lines,
+// along with the recognizer's error if it failed
+fn get_element_texts(
+    lines: &[line],
+    stroke_colors: &[Option<image::Rgb<u8>>],
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    recognizer: Option<&dyn text_recognizer>
+) -> (Vec<Option<element_text>>, Option<ocr_error>) {
+    let mut text_lines: Vec<text_line> = Vec::new();
+    for (line, stroke_color) in lines.iter().zip(stroke_colors) {
+        if let Some(stroke_color) = stroke_color {
+            text_lines.push(text_line {
+                line,
+                stroke_color: *stroke_color,
+                text: "".to_string(),
+                words: Vec::new(),
+            });
+        }
+    }
+    let mut error: Option<ocr_error> = None;
+    if let Some(recognizer) = recognizer {
+        error = transcribe_text_lines(&mut text_lines, buffer, recognizer).err();
+    }
+    let mut text_lines = text_lines.into_iter().peekable();
+    let mut texts: Vec<Option<element_text>> = Vec::new();
+    for line in lines {
+        // text_lines are in the same order as lines, so the next one is this line's if any is
+        let text = text_lines.next_if(|text_line| std::ptr::eq(text_line.line, line))
+            .map(|text_line| element_text {
+                stroke_color: text_line.stroke_color,
+                text: text_line.text,
+                words: text_line.words,
+            });
+        texts.push(text);
+    }
+    return (texts, error);
+}
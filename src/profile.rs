@@ -0,0 +1,94 @@
+use crate::color::color_difference;
+use crate::pipeline::detector_config;
+use crate::threshold::threshold_mode;
+
+// Starting points for common kinds of screenshot. A config file can name one with
+// `profile = "..."` and change only what differs, see detector_config::load
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum config_profile {
+    Default, // The hand-tuned constants
+    LightDesktop, // What the constants were tuned on, with duplicate borders merged
+    DarkMobile, // Low contrast dark themes at high pixel density
+    PhotoOfScreen, // Camera photos: noise, moire, uneven lighting and slight rotation
+}
+
+pub const CONFIG_PROFILES: [config_profile; 4] = [
+    config_profile::Default,
+    config_profile::LightDesktop,
+    config_profile::DarkMobile,
+    config_profile::PhotoOfScreen,
+];
+
+impl config_profile {
+    // Name used in configuration, e.g. "dark-mobile"
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            config_profile::Default => "default",
+            config_profile::LightDesktop => "light-desktop",
+            config_profile::DarkMobile => "dark-mobile",
+            config_profile::PhotoOfScreen => "photo-of-screen",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<config_profile> {
+        let name = name.trim().to_lowercase().replace(['_', ' '], "-");
+        return CONFIG_PROFILES.into_iter().find(|profile| profile.get_name() == name)
+    }
+
+    pub fn get_config(&self) -> detector_config {
+        let default = detector_config::default();
+        return match self {
+            config_profile::Default => default,
+            config_profile::LightDesktop => detector_config {
+                suppress_duplicates: true,
+                ..default
+            },
+            config_profile::DarkMobile => detector_config {
+                // Dark themes have weaker edges, so the threshold follows the image
+                threshold_mode: threshold_mode::Otsu,
+                hysteresis_ratio: 0.6,
+                normalise_activation: true,
+                color_difference: color_difference::Ciede2000,
+                difference_color_threshold: 20.0,
+                // Elements are two or three times the size of desktop ones
+                area_threshold: 24,
+                larger_width_threshold: 16,
+                pyramid_levels: 2,
+                suppress_duplicates: true,
+                ..default
+            },
+            config_profile::PhotoOfScreen => detector_config {
+                // Lighting changes across the photo, so the threshold follows it
+                threshold_mode: threshold_mode::Adaptive,
+                adaptive_radius: 16,
+                hysteresis_ratio: 0.5,
+                color_difference: color_difference::Ciede2000,
+                difference_color_threshold: 45.0,
+                area_threshold: 16,
+                larger_width_threshold: 12,
+                activation_threshold: 0.3,
+                suppress_duplicates: true,
+                duplicate_max_gap: 4,
+                rotated_bounds: true,
+                ..default
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for config_profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.get_name())
+    }
+}
+
+impl std::str::FromStr for config_profile {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<config_profile, String> {
+        return config_profile::from_name(name).ok_or_else(|| {
+            let names: Vec<&str> = CONFIG_PROFILES.iter().map(|profile| profile.get_name()).collect();
+            format!("unknown profile \"{}\", expected one of: {}", name, names.join(", "))
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use image::imageops::{resize, FilterType};
+use image::RgbImage;
+
+use crate::bounds::rect;
+use crate::lines::line;
+
+// Number of images in the pyramid, including the original. 1 only looks at the original.
+pub const PYRAMID_LEVELS: u32 = 1;
+// Size of each level relative to the one before it
+pub const PYRAMID_SCALE: f32 = 0.5;
+// Lines found at a coarser level overlapping a finer level's line by at least this
+// (intersection over union) are the same element, see merge_pyramid_lines
+pub const PYRAMID_IOU_THRESHOLD: f32 = 0.5;
+
+// Smallest side a level can have, smaller ones have nothing left to find
+const MIN_PYRAMID_SIZE: u32 = 16;
+
+// The image at decreasing sizes, starting with the original. The 5x5 brush and size
+// thresholds are fixed in pixels, so a large panel's soft edges that are lost at full size
+// still stand out once it's shrunk. Stops early if a level would be smaller than
+// MIN_PYRAMID_SIZE, and scales outside 0 - 1 give only the original.
+pub fn get_pyramid(buffer: &RgbImage, levels: u32, scale: f32) -> Vec<RgbImage> {
+    let mut pyramid: Vec<RgbImage> = vec![buffer.clone()];
+    if scale <= 0.0 || scale >= 1.0 {
+        return pyramid
+    }
+    for _ in 1..levels {
+        let previous = &pyramid[pyramid.len() - 1];
+        let width = (previous.width() as f32 * scale).round() as u32;
+        let height = (previous.height() as f32 * scale).round() as u32;
+        if width < MIN_PYRAMID_SIZE || height < MIN_PYRAMID_SIZE {
+            break
+        }
+        // Triangle averages the pixels each new one covers, so thin borders fade rather than vanish
+        let level = resize(previous, width, height, FilterType::Triangle);
+        pyramid.push(level);
+    }
+    return pyramid
+}
+
+// Maps the pixels of a line found in a level of size level_size back onto the original
+// image. Edges are a few pixels thick at every level, so filling in the block of original
+// pixels each covers would make coarse lines 1 / scale times too thick, and too dense for
+// sanitise_lines. Each pixel becomes the diagonal of its block instead: about 1 / scale
+// pixels, so activation (fill) is about what the edge would have at full size, while the
+// bounds still cover every block.
+pub fn scale_line_pixels(
+    pixels: &[(u32, u32)],
+    level_size: (u32, u32),
+    original_size: (u32, u32)
+) -> Vec<(u32, u32)> {
+    // First original pixel covered by level pixel i along one axis
+    let get_start = |i: u32, level: u32, original: u32| (i as u64 * original as u64 / level as u64) as u32;
+    let mut scaled: Vec<(u32, u32)> = Vec::new();
+    for (x, y) in pixels {
+        let x_start = get_start(*x, level_size.0, original_size.0);
+        let y_start = get_start(*y, level_size.1, original_size.1);
+        let block_width = get_start(x + 1, level_size.0, original_size.0) - x_start;
+        let block_height = get_start(y + 1, level_size.1, original_size.1) - y_start;
+        // From the block's top left to its bottom right
+        let steps = std::cmp::max(block_width, block_height);
+        for step in 0..steps {
+            scaled.push((x_start + step * block_width / steps, y_start + step * block_height / steps));
+        }
+    }
+    return scaled
+}
+
+fn is_overlapping(bounds: &rect, kept: &[line], iou_threshold: f32) -> bool {
+    return kept.iter().any(|kept_line| kept_line.bounds.get_iou(bounds) >= iou_threshold)
+}
+
+// Merges the lines found at each level (finest first, already scaled to the original) with
+// non-maximum suppression. Finer levels win, as their outlines are exact rather than blocks:
+// a line is dropped if it overlaps a line kept from a finer level by at least iou_threshold.
+// Lines from the same level never suppress each other, they are already separate.
+pub fn merge_pyramid_lines(levels: Vec<Vec<line>>, iou_threshold: f32) -> Vec<line> {
+    let mut merged: Vec<line> = Vec::new();
+    for level in levels {
+        let finer_count = merged.len();
+        for line in level {
+            if !is_overlapping(&line.bounds, &merged[..finer_count], iou_threshold) {
+                merged.push(line);
+            }
+        }
+    }
+    return merged
+}
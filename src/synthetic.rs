@@ -0,0 +1,324 @@
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::RgbImage;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+use crate::bounds::rect;
+use crate::classify::element_kind;
+
+// Rendered in the same fonts glyph_recognizer is trained on
+const FONTS: [&[u8]; 2] = [
+    include_bytes!("../assets/fonts/DejaVuSans.ttf"),
+    include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf"),
+];
+const WORDS: [&str; 24] = [
+    "OK", "Cancel", "Submit", "Search", "Save", "Delete", "Next", "Back", "Email", "Password",
+    "Name", "Settings", "Remember me", "Sign in", "Log out", "Accept terms", "Profile", "Help",
+    "Username", "Continue", "Show more", "Notifications", "Dark mode", "Apply",
+];
+
+// An element drawn into a synthetic screenshot, where it really is
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ground_truth_element {
+    pub kind: element_kind,
+    pub bounds: rect,
+    pub text: Option<String>, // What it says, if it is text
+}
+
+// Everything drawn into one screenshot, saved as JSON alongside it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ground_truth_image {
+    pub path: String, // Image file, relative to this file
+    pub width: u32,
+    pub height: u32,
+    pub elements: Vec<ground_truth_element>,
+}
+
+// Ranges everything random is drawn from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct synthetic_settings {
+    pub width: u32,
+    pub height: u32,
+    pub max_elements: usize, // Fewer are drawn if there isn't room
+    pub font_size_mean: f32, // Pixels, sizes are normally distributed
+    pub font_size_std: f32,
+    pub max_noise_std: f32, // Each image gets gaussian noise with a std up to this
+    pub dark_theme_chance: f64,
+}
+
+impl Default for synthetic_settings {
+    fn default() -> synthetic_settings {
+        return synthetic_settings {
+            width: 800,
+            height: 600,
+            max_elements: 20,
+            font_size_mean: 16.0,
+            font_size_std: 3.0,
+            max_noise_std: 4.0,
+            dark_theme_chance: 0.3,
+        }
+    }
+}
+
+impl synthetic_settings {
+    // Err describing the first setting generate_screenshot can't draw from, which would
+    // otherwise panic part way through
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!("screenshots must have a size, not {}x{}", self.width, self.height))
+        }
+        if !self.font_size_mean.is_finite() || self.font_size_mean <= 0.0 {
+            return Err(format!("font size mean must be above 0, not {}", self.font_size_mean))
+        }
+        if !self.font_size_std.is_finite() || self.font_size_std < 0.0 {
+            return Err(format!("font size std must be 0 or more, not {}", self.font_size_std))
+        }
+        if !self.max_noise_std.is_finite() || self.max_noise_std < 0.0 {
+            return Err(format!("max noise std must be 0 or more, not {}", self.max_noise_std))
+        }
+        if !(0.0..=1.0).contains(&self.dark_theme_chance) {
+            return Err(format!("dark theme chance must be between 0 and 1, not {}", self.dark_theme_chance))
+        }
+        return Ok(())
+    }
+}
+
+// Smallest and largest font sizes drawn, the normal distribution is clamped to these
+const MIN_FONT_SIZE: f32 = 11.0;
+const MAX_FONT_SIZE: f32 = 28.0;
+// Space kept clear around every element, so each is found as its own line
+const ELEMENT_MARGIN: u32 = 8;
+const PLACEMENT_ATTEMPTS: usize = 50;
+// Minimum luma difference between an element and what it is drawn on
+const MIN_CONTRAST: f32 = 60.0;
+
+fn get_luma(color: image::Rgb<u8>) -> f32 {
+    return 0.299 * color[0] as f32 + 0.587 * color[1] as f32 + 0.114 * color[2] as f32;
+}
+
+fn get_random_color(rng: &mut impl Rng) -> image::Rgb<u8> {
+    return image::Rgb([rng.random(), rng.random(), rng.random()]);
+}
+
+// Random colour at least MIN_CONTRAST brighter or darker than background
+fn get_contrasting_color(rng: &mut impl Rng, background: image::Rgb<u8>) -> image::Rgb<u8> {
+    loop {
+        let color = get_random_color(rng);
+        if (get_luma(color) - get_luma(background)).abs() >= MIN_CONTRAST {
+            return color
+        }
+    }
+}
+
+fn fill_rect(buffer: &mut RgbImage, rect: &rect, color: image::Rgb<u8>) {
+    for y in rect.y..=rect.bottom() {
+        for x in rect.x..=rect.right() {
+            buffer.put_pixel(x, y, color);
+        }
+    }
+}
+
+// Border thickness pixels wide just inside rect
+fn draw_border(buffer: &mut RgbImage, rect: &rect, thickness: u32, color: image::Rgb<u8>) {
+    for y in rect.y..=rect.bottom() {
+        for x in rect.x..=rect.right() {
+            let inset = (x - rect.x).min(y - rect.y).min(rect.right() - x).min(rect.bottom() - y);
+            if inset < thickness {
+                buffer.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+// Size of text once drawn: (width, height), height running from the font's ascent to descent
+fn measure_text(font: &FontRef, size: f32, text: &str) -> (u32, u32) {
+    let scaled_font = font.as_scaled(PxScale::from(size));
+    let width: f32 = text.chars().map(|character| scaled_font.h_advance(scaled_font.glyph_id(character))).sum();
+    return (width.ceil() as u32, scaled_font.height().ceil() as u32)
+}
+
+// Draws text with its top left at (x, y), blending by coverage so edges are anti-aliased.
+// Returns the box around the drawn ink, None if nothing was drawn.
+fn draw_text(
+    buffer: &mut RgbImage,
+    font: &FontRef,
+    size: f32,
+    text: &str,
+    (x, y): (u32, u32),
+    color: image::Rgb<u8>
+) -> Option<rect> {
+    let scaled_font = font.as_scaled(PxScale::from(size));
+    let mut caret = x as f32;
+    let mut inked: Vec<(u32, u32)> = Vec::new();
+    for character in text.chars() {
+        let glyph_id = scaled_font.glyph_id(character);
+        let glyph = glyph_id.with_scale_and_position(
+            PxScale::from(size),
+            ab_glyph::point(caret, y as f32 + scaled_font.ascent())
+        );
+        caret += scaled_font.h_advance(glyph_id);
+        let Some(outline) = scaled_font.outline_glyph(glyph) else { continue };
+        let bounds = outline.px_bounds();
+        outline.draw(|glyph_x, glyph_y, coverage| {
+            let (pixel_x, pixel_y) = (bounds.min.x as i64 + glyph_x as i64, bounds.min.y as i64 + glyph_y as i64);
+            if pixel_x < 0 || pixel_y < 0 || pixel_x >= buffer.width() as i64 || pixel_y >= buffer.height() as i64 {
+                return
+            }
+            let pixel = buffer.get_pixel_mut(pixel_x as u32, pixel_y as u32);
+            for channel in 0..3 {
+                pixel[channel] = (pixel[channel] as f32 * (1.0 - coverage) + color[channel] as f32 * coverage).round() as u8;
+            }
+            // Faint edges are lost against the background, so aren't counted as part of the text
+            if coverage >= 0.5 {
+                inked.push((pixel_x as u32, pixel_y as u32));
+            }
+        });
+    }
+    return rect::from_points(&inked)
+}
+
+// Everything needed to draw the text of one element
+struct text_style<'a> {
+    font: &'a FontRef<'a>,
+    size: f32,
+    text: &'static str,
+}
+
+fn get_text_style<'a>(rng: &mut impl Rng, fonts: &'a [FontRef<'a>], settings: &synthetic_settings) -> text_style<'a> {
+    let size_distribution = Normal::new(settings.font_size_mean, settings.font_size_std)
+        .expect("settings are validated");
+    return text_style {
+        font: &fonts[rng.random_range(0..fonts.len())],
+        size: size_distribution.sample(rng).clamp(MIN_FONT_SIZE, MAX_FONT_SIZE).round(),
+        text: WORDS[rng.random_range(0..WORDS.len())],
+    }
+}
+
+// Draws text, adding it to elements if anything was drawn
+fn add_text(
+    buffer: &mut RgbImage,
+    elements: &mut Vec<ground_truth_element>,
+    style: &text_style,
+    position: (u32, u32),
+    color: image::Rgb<u8>
+) {
+    if let Some(bounds) = draw_text(buffer, style.font, style.size, style.text, position, color) {
+        elements.push(ground_truth_element {
+            kind: element_kind::TextLabel,
+            bounds,
+            text: Some(style.text.to_string()),
+        });
+    }
+}
+
+// Size of an element of the given kind holding text of text_size, including any label
+fn get_element_size(rng: &mut impl Rng, kind: element_kind, text_size: (u32, u32)) -> (u32, u32) {
+    let (text_width, text_height) = text_size;
+    return match kind {
+        element_kind::Button => (text_width + rng.random_range(16..40), text_height + rng.random_range(8..20)),
+        element_kind::TextField => (text_width + rng.random_range(60..200), text_height + rng.random_range(8..16)),
+        // Box then a gap then the label
+        element_kind::Checkbox => (text_height + 8 + text_width, text_height),
+        _ => (text_width, text_height),
+    }
+}
+
+// Draws an element of the given kind filling rect, returning what was drawn
+fn draw_element(
+    rng: &mut impl Rng,
+    buffer: &mut RgbImage,
+    kind: element_kind,
+    rect: rect,
+    style: &text_style,
+    background: image::Rgb<u8>
+) -> Vec<ground_truth_element> {
+    let mut elements: Vec<ground_truth_element> = Vec::new();
+    let (text_width, text_height) = measure_text(style.font, style.size, style.text);
+    match kind {
+        element_kind::Button => {
+            let fill = get_contrasting_color(rng, background);
+            fill_rect(buffer, &rect, fill);
+            if rng.random_bool(0.5) {
+                draw_border(buffer, &rect, rng.random_range(1..3), get_contrasting_color(rng, fill));
+            }
+            elements.push(ground_truth_element { kind, bounds: rect, text: None });
+            let position = (rect.x + (rect.width - text_width) / 2, rect.y + (rect.height - text_height) / 2);
+            add_text(buffer, &mut elements, style, position, get_contrasting_color(rng, fill));
+        },
+        element_kind::TextField => {
+            let border = get_contrasting_color(rng, background);
+            draw_border(buffer, &rect, 1, border);
+            elements.push(ground_truth_element { kind, bounds: rect, text: None });
+            // Placeholder text
+            let position = (rect.x + 8, rect.y + (rect.height - text_height) / 2);
+            add_text(buffer, &mut elements, style, position, get_contrasting_color(rng, background));
+        },
+        element_kind::Checkbox => {
+            let color = get_contrasting_color(rng, background);
+            let box_rect = rect::new(rect.x, rect.y, rect.height, rect.height);
+            draw_border(buffer, &box_rect, rng.random_range(1..3), color);
+            if rng.random_bool(0.5) {
+                let inset = box_rect.width / 4;
+                let checked = rect::new(box_rect.x + inset, box_rect.y + inset, box_rect.width - 2 * inset, box_rect.height - 2 * inset);
+                fill_rect(buffer, &checked, color);
+            }
+            elements.push(ground_truth_element { kind, bounds: box_rect, text: None });
+            add_text(buffer, &mut elements, style, (rect.x + rect.height + 8, rect.y), color);
+        },
+        _ => add_text(buffer, &mut elements, style, (rect.x, rect.y), get_contrasting_color(rng, background)),
+    }
+    return elements
+}
+
+fn add_noise(rng: &mut impl Rng, buffer: &mut RgbImage, std: f32) {
+    let Ok(noise) = Normal::new(0.0, std) else { return };
+    for pixel in buffer.pixels_mut() {
+        for channel in 0..3 {
+            let value: f32 = pixel[channel] as f32 + noise.sample(rng);
+            pixel[channel] = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+// Draws a random layout of buttons, text fields, checkboxes and labels, returning the
+// screenshot and where each element is. Elements never overlap, see ELEMENT_MARGIN.
+// Panics if settings fail validate.
+pub fn generate_screenshot(rng: &mut impl Rng, settings: &synthetic_settings) -> (RgbImage, Vec<ground_truth_element>) {
+    let fonts: Vec<FontRef> = FONTS.iter()
+        .map(|font_data| FontRef::try_from_slice(font_data).expect("bundled fonts are valid"))
+        .collect();
+    let background = if rng.random_bool(settings.dark_theme_chance) {
+        image::Rgb([rng.random_range(0..48), rng.random_range(0..48), rng.random_range(0..48)])
+    } else {
+        image::Rgb([rng.random_range(208..=255), rng.random_range(208..=255), rng.random_range(208..=255)])
+    };
+    let mut buffer = RgbImage::from_pixel(settings.width, settings.height, background);
+    let kinds = [element_kind::Button, element_kind::TextField, element_kind::Checkbox, element_kind::TextLabel];
+
+    let mut taken: Vec<rect> = Vec::new();
+    let mut elements: Vec<ground_truth_element> = Vec::new();
+    for _ in 0..settings.max_elements {
+        let kind = kinds[rng.random_range(0..kinds.len())];
+        let style = get_text_style(rng, &fonts, settings);
+        let (width, height) = get_element_size(rng, kind, measure_text(style.font, style.size, style.text));
+        if width + 2 * ELEMENT_MARGIN > settings.width || height + 2 * ELEMENT_MARGIN > settings.height {
+            continue
+        }
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            let x = rng.random_range(ELEMENT_MARGIN..=settings.width - width - ELEMENT_MARGIN);
+            let y = rng.random_range(ELEMENT_MARGIN..=settings.height - height - ELEMENT_MARGIN);
+            let rect = rect::new(x, y, width, height);
+            let padded = rect::new(x - ELEMENT_MARGIN, y - ELEMENT_MARGIN, width + 2 * ELEMENT_MARGIN, height + 2 * ELEMENT_MARGIN);
+            if taken.iter().any(|other| other.intersection(&padded).is_some()) {
+                continue
+            }
+            taken.push(rect);
+            elements.extend(draw_element(rng, &mut buffer, kind, rect, &style, background));
+            break
+        }
+    }
+    let noise_std = rng.random_range(0.0..=settings.max_noise_std);
+    add_noise(rng, &mut buffer, noise_std);
+    return (buffer, elements)
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use image::{ImageBuffer, Pixel};
+
+use crate::color::{color_difference, get_color_difference};
+use crate::draw::get_line_image;
+use crate::frequency::{get_high_frequency_share, get_region_spectrum};
+use crate::lines::line;
+use crate::ocr::{ocr_error, recognized_word, text_recognizer};
+
+pub struct text_line<'a> {
+    pub line: &'a line,
+    pub stroke_color: image::Rgb<u8>,
+    pub text: String,
+    pub words: Vec<recognized_word>, // Boxes are relative to the whole image
+}
+
+// Colours closer than this are the same colour, see get_line_colors
+pub const DIFFERENCE_COLOR_THRESH: f32 = 30.0;
+
+// Groups the line's pixels into colours no more than color_threshold apart (see
+// DIFFERENCE_COLOR_THRESH), measured by color_difference, with how many pixels are in each
+pub fn get_line_colors(
+    line: &line,
+    buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    color_difference: color_difference,
+    color_threshold: f32
+) -> HashMap<image::Rgb<u8>, u32> {
+    let mut color_freqs: HashMap<image::Rgb<u8>, u32> = HashMap::new();
+    for pixel in &line.pixels {
+        let pixel = buffer.get_pixel(pixel.0, pixel.1);
+        let curr_color = pixel.channels();
+        let mut match_found = false;
+        for other_color in color_freqs.keys() {
+            let difference = get_color_difference(pixel, other_color, color_difference);
+            if difference <= color_threshold {
+                match_found = true;
+                *color_freqs.entry(*other_color).or_insert(0) += 1;
+                break
+            }
+        }
+        if !match_found {
+            color_freqs.insert(image::Rgb::<u8>([curr_color[0], curr_color[1], curr_color[2]]), 1);
+        }
+    }
+    return color_freqs;
+}
+
+pub fn get_most_common_color(color_freqs: &HashMap<image::Rgb<u8>, u32>) -> image::Rgb<u8> {
+    let mut most_common = image::Rgb::<u8>([0, 0, 0]);
+    let mut highest_freq: u32 = 0;
+    for (color, freq) in color_freqs.iter() {
+        if *freq > highest_freq {
+            most_common = *color;
+            highest_freq = *freq;
+        }
+    }
+    return most_common;
+}
+
+// Fraction of a line's power which must be high frequency for it to be text, see
+// get_high_frequency_share. 0.0 skips the check; text is usually above 0.1, gradients and
+// photos well below.
+pub const TEXT_HIGH_FREQUENCY_SHARE: f32 = 0.0;
+
+// Returns all lines it suspects to contain text, by examining the original image.
+// min_high_frequency_share rules out smooth two colour lines, like gradients and soft
+// shadows, see detector_config::text_high_frequency_share
+pub fn get_text_lines<'a>(
+    lines: &'a [line],
+    img_buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    color_difference: color_difference,
+    color_threshold: f32,
+    min_high_frequency_share: f32
+) -> Vec<text_line<'a>> {
+    // List containing all lines which are text
+    let mut text_lines: Vec<text_line> = Vec::new();
+
+    for line in lines {
+        let color_freqs = get_line_colors(line, img_buffer, color_difference, color_threshold);
+        if color_freqs.keys().len() != 2 {
+            continue
+        }
+        let stroke_color = get_most_common_color(&color_freqs);
+        // Checked after the colours as the FFT is the slower test
+        if min_high_frequency_share > 0.0
+            && get_high_frequency_share(&get_region_spectrum(img_buffer, &line.bounds)) < min_high_frequency_share {
+            continue
+        }
+        text_lines.push(
+            text_line {
+                line,
+                stroke_color,
+                text: "".to_string(), 
+                words: Vec::new(),
+            }
+        )
+    }
+    text_lines
+}
+
+// Fills in the text and words of each text_line by running the recognizer over its crop.
+// Stops at the first line the recognizer fails on.
+pub fn transcribe_text_lines(
+    text_lines: &mut [text_line],
+    img_buffer: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    recognizer: &dyn text_recognizer
+) -> Result<(), ocr_error> {
+    for text_line in text_lines {
+        let line_image = get_line_image(text_line.line, img_buffer);
+        let mut words = recognizer.recognize_words(&line_image)?;
+        // Crops start at the top left of the line's bounds, see get_line_image
+        for word in words.iter_mut() {
+            word.x += text_line.line.bounds.x;
+            word.y += text_line.line.bounds.y;
+        }
+        let texts: Vec<&str> = words.iter().map(|word| word.text.as_str()).collect();
+        text_line.text = texts.join(" ");
+        text_line.words = words;
+    }
+    return Ok(())
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+use crate::activation::{activation_map, activation_stats, HISTOGRAM_BIN_WIDTH};
+
+// How the threshold get_lines uses is chosen. A fixed threshold is tuned for one kind of
+// screenshot; the others adapt to dark themes and low contrast UIs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum threshold_mode {
+    Fixed, // detector_config::line_threshold
+    Otsu, // Best split of the histogram into two classes
+    Percentile, // Activation a given percentage of activated pixels are below
+    MeanStd, // Mean + k standard deviations of activated pixels
+    Adaptive, // Per pixel, from the mean activation around it, see get_local_thresholds
+}
+
+pub const THRESHOLD_MODES: [threshold_mode; 5] = [
+    threshold_mode::Fixed,
+    threshold_mode::Otsu,
+    threshold_mode::Percentile,
+    threshold_mode::MeanStd,
+    threshold_mode::Adaptive,
+];
+
+impl threshold_mode {
+    // Name used in configuration, e.g. "mean_std"
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            threshold_mode::Fixed => "fixed",
+            threshold_mode::Otsu => "otsu",
+            threshold_mode::Percentile => "percentile",
+            threshold_mode::MeanStd => "mean_std",
+            threshold_mode::Adaptive => "adaptive",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<threshold_mode> {
+        let name = name.trim().to_lowercase().replace('-', "_");
+        return THRESHOLD_MODES.into_iter().find(|mode| mode.get_name() == name)
+    }
+}
+
+impl std::fmt::Display for threshold_mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.get_name())
+    }
+}
+
+impl std::str::FromStr for threshold_mode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<threshold_mode, String> {
+        return threshold_mode::from_name(name).ok_or_else(|| {
+            let names: Vec<&str> = THRESHOLD_MODES.iter().map(|mode| mode.get_name()).collect();
+            format!("unknown threshold mode \"{}\", expected one of: {}", name, names.join(", "))
+        })
+    }
+}
+
+pub const THRESHOLD_PERCENTILE: f32 = 75.0;
+pub const THRESHOLD_STD_COUNT: f32 = 0.5; // k in mean + k * std
+pub const ADAPTIVE_RADIUS: u32 = 8; // Window is (2 * radius + 1) pixels square
+// Added to the local mean, so flat areas (mean 0) still need some activation
+pub const ADAPTIVE_OFFSET: f32 = 10.0;
+// Otsu, Percentile and MeanStd pick a threshold between the strong centre of each edge and
+// its weaker sides, which alone leaves outlines too thin for sanitise_lines. Lines seeded
+// above it grow through pixels above this fraction of it instead, unless hysteresis_ratio
+// is lower still.
+pub const AUTOMATIC_GROW_RATIO: f32 = 0.25;
+
+// Threshold separating the histogram into two classes with the largest variance between
+// them (Otsu's method). Returns the lowest activation in the upper class, infinity if the
+// histogram can't be split (it is empty or everything is in one bin), so nothing is kept.
+pub fn get_otsu_threshold(histogram: &[u32]) -> f32 {
+    let total: f64 = histogram.iter().map(|count| *count as f64).sum();
+    let weighted_total: f64 = histogram.iter().enumerate().map(|(bin, count)| bin as f64 * *count as f64).sum();
+    let mut lower_count: f64 = 0.0;
+    let mut lower_weighted: f64 = 0.0;
+    let mut best_variance: f64 = -1.0;
+    let mut best_bin: Option<usize> = None;
+    for (bin, count) in histogram.iter().enumerate() {
+        lower_count += *count as f64;
+        lower_weighted += bin as f64 * *count as f64;
+        let upper_count = total - lower_count;
+        if lower_count == 0.0 || upper_count == 0.0 {
+            continue
+        }
+        let lower_mean = lower_weighted / lower_count;
+        let upper_mean = (weighted_total - lower_weighted) / upper_count;
+        let variance = lower_count * upper_count * (lower_mean - upper_mean).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_bin = Some(bin);
+        }
+    }
+    return best_bin.map_or(f32::INFINITY, |bin| (bin + 1) as f32 * HISTOGRAM_BIN_WIDTH)
+}
+
+// Activation percentile% of activated pixels are below, 0 - 100. Infinity if nothing is activated.
+pub fn get_percentile_threshold(activation_map: &activation_map, percentile: f32) -> f32 {
+    let mut activated: Vec<f32> = activation_map.values().iter()
+        .filter(|activation| **activation > 0.0)
+        .cloned()
+        .collect();
+    if activated.is_empty() {
+        return f32::INFINITY
+    }
+    activated.sort_by(f32::total_cmp);
+    let index = ((percentile / 100.0).clamp(0.0, 1.0) * (activated.len() - 1) as f32).round() as usize;
+    return activated[index]
+}
+
+// Infinity if nothing is activated
+pub fn get_mean_std_threshold(stats: &activation_stats, std_count: f32) -> f32 {
+    if stats.activation_count == 0 {
+        return f32::INFINITY
+    }
+    return stats.avg_activation + std_count * stats.std_activation
+}
+
+// Threshold for every pixel: the mean activation in the window around it plus offset.
+// Uses a summed area table, so is the same speed for any radius.
+pub fn get_local_thresholds(activation_map: &activation_map, radius: u32, offset: f32) -> activation_map {
+    let (width, height) = (activation_map.width() as usize, activation_map.height() as usize);
+    // sums[(y * (width + 1)) + x] is the total of everything above and left of (x, y)
+    let mut sums: Vec<f64> = vec![0.0; (width + 1) * (height + 1)];
+    for y in 0..height {
+        let mut row_total: f64 = 0.0;
+        for x in 0..width {
+            let activation = activation_map.get(x as u32, y as u32);
+            if !activation.is_nan() {
+                row_total += activation as f64;
+            }
+            sums[(y + 1) * (width + 1) + x + 1] = sums[y * (width + 1) + x + 1] + row_total;
+        }
+    }
+
+    let radius = radius as usize;
+    let mut thresholds = activation_map::new(width as u32, height as u32);
+    for y in 0..height {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+        for x in 0..width {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+            let total = sums[y1 * (width + 1) + x1] - sums[y0 * (width + 1) + x1]
+                - sums[y1 * (width + 1) + x0] + sums[y0 * (width + 1) + x0];
+            let mean = total / ((x1 - x0) * (y1 - y0)) as f64;
+            thresholds.set(x as u32, y as u32, mean as f32 + offset);
+        }
+    }
+    return thresholds
+}
@@ -0,0 +1,252 @@
+use image::RgbImage;
+use rand::Rng;
+
+use crate::edges::edge_operator;
+use crate::eval::{evaluate, eval_report, get_detections, labelled_image};
+use crate::pipeline::{detector, detector_config};
+use crate::threshold::threshold_mode;
+
+// How candidate configs are chosen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum search_strategy {
+    Grid, // Every combination of evenly spaced values
+    Random, // Values drawn uniformly from each range
+}
+
+pub const SEARCH_STRATEGIES: [search_strategy; 2] = [search_strategy::Grid, search_strategy::Random];
+
+impl search_strategy {
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            search_strategy::Grid => "grid",
+            search_strategy::Random => "random",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<search_strategy> {
+        let name = name.trim().to_lowercase();
+        return SEARCH_STRATEGIES.into_iter().find(|strategy| strategy.get_name() == name)
+    }
+}
+
+impl std::fmt::Display for search_strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.get_name())
+    }
+}
+
+impl std::str::FromStr for search_strategy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<search_strategy, String> {
+        return search_strategy::from_name(name)
+            .ok_or_else(|| format!("unknown search strategy \"{}\", expected grid or random", name))
+    }
+}
+
+// What a config is scored on, higher is better
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum tune_metric {
+    F1, // Over every kind
+    MeanAveragePrecision,
+}
+
+pub const TUNE_METRICS: [tune_metric; 2] = [tune_metric::F1, tune_metric::MeanAveragePrecision];
+
+impl tune_metric {
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            tune_metric::F1 => "f1",
+            tune_metric::MeanAveragePrecision => "map",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<tune_metric> {
+        let name = name.trim().to_lowercase();
+        return TUNE_METRICS.into_iter().find(|metric| metric.get_name() == name)
+    }
+
+    pub fn get_score(&self, report: &eval_report) -> f32 {
+        return match self {
+            tune_metric::F1 => report.f1,
+            tune_metric::MeanAveragePrecision => report.mean_average_precision,
+        }
+    }
+}
+
+impl std::fmt::Display for tune_metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.get_name())
+    }
+}
+
+impl std::str::FromStr for tune_metric {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<tune_metric, String> {
+        return tune_metric::from_name(name)
+            .ok_or_else(|| format!("unknown metric \"{}\", expected f1 or map", name))
+    }
+}
+
+// Inclusive range searched for each parameter, everything else stays as the base config has it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct search_space {
+    pub line_threshold: (f32, f32),
+    pub immediate_neighbour_weight: (f32, f32),
+    pub area_threshold: (u32, u32),
+    pub larger_width_threshold: (u32, u32),
+    pub activation_threshold: (f32, f32),
+    pub difference_color_threshold: (f32, f32),
+}
+
+impl Default for search_space {
+    fn default() -> search_space {
+        return search_space {
+            line_threshold: (5.0, 60.0),
+            immediate_neighbour_weight: (0.0, 1.0),
+            area_threshold: (1, 64),
+            larger_width_threshold: (1, 32),
+            activation_threshold: (0.0, 0.8),
+            difference_color_threshold: (10.0, 80.0),
+        }
+    }
+}
+
+// steps evenly spaced values from min to max, just the middle if steps is 1
+fn get_steps((min, max): (f32, f32), steps: usize) -> Vec<f32> {
+    if steps <= 1 {
+        return vec![(min + max) / 2.0]
+    }
+    return (0..steps).map(|step| min + (max - min) * step as f32 / (steps - 1) as f32).collect()
+}
+
+// Every config in configs once for each value
+fn expand(
+    configs: Vec<detector_config>,
+    values: &[f32],
+    set_value: impl Fn(&mut detector_config, f32)
+) -> Vec<detector_config> {
+    let mut expanded: Vec<detector_config> = Vec::new();
+    for config in configs {
+        for value in values {
+            let mut config = config.clone();
+            set_value(&mut config, *value);
+            expanded.push(config);
+        }
+    }
+    return expanded
+}
+
+// Whole number steps, without repeats when the range is narrower than steps
+fn get_whole_steps((min, max): (u32, u32), steps: usize) -> Vec<f32> {
+    let mut values: Vec<f32> = get_steps((min as f32, max as f32), steps).iter().map(|value| value.round()).collect();
+    values.dedup();
+    return values
+}
+
+// Most configs grid search may try, as each is run over the whole dataset
+pub const MAX_GRID_CONFIGS: usize = 10_000;
+
+// Values tried for one parameter and how to set it
+type grid_axis = (Vec<f32>, fn(&mut detector_config, f32));
+
+// Values for each parameter the base config uses. line_threshold is only read by the
+// Fixed threshold mode and immediate_neighbour_weight by the Brush operator, so sweeping
+// them otherwise would only repeat the same detections.
+fn get_grid_axes(base: &detector_config, space: &search_space, steps: usize) -> Vec<grid_axis> {
+    let mut axes: Vec<grid_axis> = Vec::new();
+    if base.threshold_mode == threshold_mode::Fixed {
+        axes.push((get_steps(space.line_threshold, steps), |config, value| config.line_threshold = value));
+    }
+    if base.edge_operator == edge_operator::Brush {
+        axes.push((get_steps(space.immediate_neighbour_weight, steps), |config, value| {
+            config.immediate_neighbour_weight = value
+        }));
+    }
+    axes.push((get_whole_steps(space.area_threshold, steps), |config, value| config.area_threshold = value as u32));
+    axes.push((get_whole_steps(space.larger_width_threshold, steps), |config, value| {
+        config.larger_width_threshold = value as u32
+    }));
+    axes.push((get_steps(space.activation_threshold, steps), |config, value| config.activation_threshold = value));
+    axes.push((get_steps(space.difference_color_threshold, steps), |config, value| {
+        config.difference_color_threshold = value
+    }));
+    return axes
+}
+
+// Number of configs get_grid_configs gives, without building them. Saturates rather than
+// overflowing, so it can be checked against MAX_GRID_CONFIGS for any steps.
+pub fn get_grid_size(base: &detector_config, space: &search_space, steps: usize) -> usize {
+    return get_grid_axes(base, space, steps).iter().fold(1, |size, (values, _)| size.saturating_mul(values.len()))
+}
+
+// Grid search: every combination of steps values per parameter the base config uses, so
+// up to steps^6 configs, see get_grid_size
+pub fn get_grid_configs(base: &detector_config, space: &search_space, steps: usize) -> Vec<detector_config> {
+    let mut configs: Vec<detector_config> = vec![base.clone()];
+    for (values, set_value) in get_grid_axes(base, space, steps) {
+        configs = expand(configs, &values, set_value);
+    }
+    return configs
+}
+
+// Random search: count configs with every parameter the base config uses drawn uniformly
+// from its range
+pub fn get_random_configs(
+    base: &detector_config,
+    space: &search_space,
+    count: usize,
+    rng: &mut impl Rng
+) -> Vec<detector_config> {
+    let mut configs: Vec<detector_config> = Vec::new();
+    for _ in 0..count {
+        let mut config = base.clone();
+        // Left alone when unused, as in get_grid_axes
+        if base.threshold_mode == threshold_mode::Fixed {
+            config.line_threshold = rng.random_range(space.line_threshold.0..=space.line_threshold.1);
+        }
+        if base.edge_operator == edge_operator::Brush {
+            config.immediate_neighbour_weight =
+                rng.random_range(space.immediate_neighbour_weight.0..=space.immediate_neighbour_weight.1);
+        }
+        config.area_threshold = rng.random_range(space.area_threshold.0..=space.area_threshold.1);
+        config.larger_width_threshold = rng.random_range(space.larger_width_threshold.0..=space.larger_width_threshold.1);
+        config.activation_threshold = rng.random_range(space.activation_threshold.0..=space.activation_threshold.1);
+        config.difference_color_threshold =
+            rng.random_range(space.difference_color_threshold.0..=space.difference_color_threshold.1);
+        configs.push(config);
+    }
+    return configs
+}
+
+pub struct tune_result {
+    pub config: detector_config,
+    pub score: f32,
+    pub report: eval_report,
+}
+
+// Evaluates every candidate over the dataset (images in the same order), returning the
+// best scoring. Text isn't read, as only boxes and kinds are scored. on_scored is called
+// with each candidate's index and score as it finishes, for progress.
+pub fn tune(
+    dataset: &[labelled_image],
+    images: &[RgbImage],
+    candidates: Vec<detector_config>,
+    iou_threshold: f32,
+    metric: tune_metric,
+    mut on_scored: impl FnMut(usize, f32)
+) -> Option<tune_result> {
+    let mut best: Option<tune_result> = None;
+    for (i, config) in candidates.into_iter().enumerate() {
+        let detector = detector::new(config).with_recognizer(None);
+        let detections: Vec<_> = images.iter().map(|buffer| get_detections(&detector, buffer)).collect();
+        let report = evaluate(dataset, &detections, iou_threshold);
+        let score = metric.get_score(&report);
+        on_scored(i, score);
+        if best.as_ref().is_none_or(|best| score > best.score) {
+            best = Some(tune_result { config: detector.config().clone(), score, report });
+        }
+    }
+    return best
+}
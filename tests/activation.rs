@@ -0,0 +1,75 @@
+use image::{ImageBuffer, Rgb, RgbImage};
+use nea_element_detection::activation::{
+    activation_map, brush_difference_filter, difference_filter, get_pixel_activation, get_row_activations,
+    IMMEDIATE_NEIGHBOUR_WEIGHT
+};
+
+// Odd sizes and single rows/columns catch rows being split or shifted in the wrong place
+const SIZES: [(u32, u32); 7] = [(97, 61), (1, 40), (40, 1), (2, 2), (3, 5), (1, 1), (0, 0)];
+
+fn get_test_image(width: u32, height: u32) -> RgbImage {
+    return RgbImage::from_fn(width, height, |x, y| {
+        let value = x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503);
+        Rgb([value as u8, (value >> 8) as u8, (x * 7 + y * 3) as u8])
+    });
+}
+
+// Compared as bits so NaNs (1x1 images have no neighbours) count as equal
+fn is_same_map(a: &activation_map, b: &activation_map) -> bool {
+    return a.width() == b.width()
+        && a.height() == b.height()
+        && a.values().iter().zip(b.values()).all(|(a, b)| a.to_bits() == b.to_bits());
+}
+
+fn reference_filter(image: &RgbImage, immediate_neighbour_weight: f32) -> activation_map {
+    return difference_filter(image, &move |buffer: &ImageBuffer<Rgb<u8>, Vec<u8>>, x, y| {
+        get_pixel_activation(buffer, x, y, immediate_neighbour_weight)
+    });
+}
+
+#[test]
+fn row_activations_match_get_pixel_activation() {
+    for weight in [IMMEDIATE_NEIGHBOUR_WEIGHT, 0.0, 1.0, 0.37] {
+        for (width, height) in SIZES {
+            let image = get_test_image(width, height);
+            let mut activations = vec![0.0; width as usize];
+            for y in 0..height {
+                get_row_activations(&image, y, weight, &mut activations);
+                for x in 0..width {
+                    let expected = get_pixel_activation(&image, x, y, weight);
+                    let found = activations[x as usize];
+                    assert_eq!(
+                        found.to_bits(), expected.to_bits(),
+                        "({}, {}) in {}x{}: {} != {}", x, y, width, height, found, expected
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn brush_filter_matches_difference_filter() {
+    for (width, height) in SIZES {
+        let image = get_test_image(width, height);
+        let expected = reference_filter(&image, IMMEDIATE_NEIGHBOUR_WEIGHT);
+        let found = brush_difference_filter(&image, IMMEDIATE_NEIGHBOUR_WEIGHT);
+        assert!(is_same_map(&found, &expected), "outputs differ for {}x{}", width, height);
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_filter_matches_serial() {
+    use nea_element_detection::activation::par_difference_filter;
+
+    let difference_function = |buffer: &ImageBuffer<Rgb<u8>, Vec<u8>>, x, y| {
+        get_pixel_activation(buffer, x, y, IMMEDIATE_NEIGHBOUR_WEIGHT)
+    };
+    for (width, height) in SIZES {
+        let image = get_test_image(width, height);
+        let serial = difference_filter(&image, &difference_function);
+        let parallel = par_difference_filter(&image, &difference_function);
+        assert!(is_same_map(&serial, &parallel), "outputs differ for {}x{}", width, height);
+    }
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use nea_element_detection::classify::{classify, element_features, get_elements_features};
+use nea_element_detection::lines::line;
+use nea_element_detection::element_kind;
+use common::{get_line, get_outline};
+
+// Features of a plain shape: two colours, not text and with nothing inside or around it
+fn get_features(width: u32, height: u32, fill_ratio: f32, corner_fill: f32) -> element_features {
+    return element_features {
+        width,
+        height,
+        aspect: width as f32 / height as f32,
+        fill_ratio,
+        corner_fill,
+        color_count: 2,
+        is_text: false,
+        child_count: 0,
+        text_child_count: 0,
+        parent_count: 0,
+    };
+}
+
+fn with_text_child(mut features: element_features) -> element_features {
+    features.child_count = 1;
+    features.text_child_count = 1;
+    return features;
+}
+
+fn assert_kind(features: &element_features, expected: element_kind) {
+    let (kind, confidence) = classify(features);
+    assert_eq!(kind, expected);
+    assert!(confidence > 0.5 && confidence <= 1.0, "{:?} with confidence {}", kind, confidence);
+}
+
+// Either another kind fits better, or nothing fits and classify guessed with no confidence
+fn assert_not_kind(features: &element_features, unexpected: element_kind) {
+    let (kind, confidence) = classify(features);
+    assert!(kind != unexpected || confidence == 0.0, "{:?} with confidence {}", kind, confidence);
+}
+
+#[test]
+fn buttons_are_hollow_boxes_around_text() {
+    let button = with_text_child(get_features(100, 30, 0.1, 1.0));
+    assert_kind(&button, element_kind::Button);
+    // Solid, so not an outline
+    assert_not_kind(&with_text_child(get_features(100, 30, 0.9, 1.0)), element_kind::Button);
+    // Too tall
+    assert_not_kind(&with_text_child(get_features(200, 120, 0.1, 1.0)), element_kind::Button);
+}
+
+#[test]
+fn text_fields_are_long_square_cornered_boxes() {
+    assert_kind(&get_features(200, 30, 0.05, 1.0), element_kind::TextField);
+    // Rounded ends, like a pill shaped button
+    assert_not_kind(&get_features(200, 30, 0.05, 0.0), element_kind::TextField);
+    // Not long enough
+    assert_not_kind(&get_features(60, 30, 0.05, 1.0), element_kind::TextField);
+}
+
+#[test]
+fn checkboxes_are_small_square_cornered_squares() {
+    assert_kind(&get_features(16, 16, 0.25, 1.0), element_kind::Checkbox);
+    // Round
+    assert_not_kind(&get_features(16, 16, 0.25, 0.0), element_kind::Checkbox);
+    // Too big
+    assert_not_kind(&get_features(60, 60, 0.25, 1.0), element_kind::Checkbox);
+}
+
+#[test]
+fn radio_buttons_are_small_circles() {
+    assert_kind(&get_features(16, 16, 0.25, 0.0), element_kind::RadioButton);
+    // Not round enough to be a circle
+    assert_not_kind(&get_features(16, 40, 0.25, 0.0), element_kind::RadioButton);
+    // Filled in
+    assert_not_kind(&get_features(16, 16, 0.9, 0.0), element_kind::RadioButton);
+}
+
+#[test]
+fn icons_are_small_solid_shapes() {
+    assert_kind(&get_features(24, 24, 0.6, 0.5), element_kind::Icon);
+    // Too sparse
+    assert_not_kind(&get_features(24, 24, 0.2, 0.5), element_kind::Icon);
+    // Too big
+    assert_not_kind(&get_features(200, 200, 0.6, 0.5), element_kind::Icon);
+}
+
+#[test]
+fn images_are_large_solid_and_colourful() {
+    let mut image = get_features(200, 150, 0.9, 1.0);
+    image.color_count = 12;
+    assert_kind(&image, element_kind::Image);
+    // Only two colours, e.g. a solid panel
+    assert_not_kind(&get_features(200, 150, 0.9, 1.0), element_kind::Image);
+    // Too small
+    image.width = 20;
+    image.height = 20;
+    image.aspect = 1.0;
+    assert_not_kind(&image, element_kind::Image);
+}
+
+#[test]
+fn text_labels_are_text_with_nothing_inside() {
+    let mut label = get_features(80, 14, 0.3, 0.5);
+    label.is_text = true;
+    assert_kind(&label, element_kind::TextLabel);
+    // Text found by colours, but with something inside it
+    assert_not_kind(&with_text_child(label), element_kind::TextLabel);
+    // Missed by get_text_lines but still a short, wide, solid run
+    let (kind, _) = classify(&get_features(60, 12, 0.6, 0.5));
+    assert_eq!(kind, element_kind::TextLabel);
+    assert_not_kind(&get_features(60, 12, 0.1, 0.5), element_kind::TextLabel);
+}
+
+#[test]
+fn separators_are_long_thin_lines() {
+    assert_kind(&get_features(300, 2, 1.0, 1.0), element_kind::Separator);
+    assert_kind(&get_features(2, 300, 1.0, 1.0), element_kind::Separator);
+    // Too thick
+    assert_not_kind(&get_features(300, 10, 1.0, 1.0), element_kind::Separator);
+    // Too short for its thickness
+    assert_not_kind(&get_features(12, 2, 1.0, 1.0), element_kind::Separator);
+}
+
+#[test]
+fn containers_are_large_outlines_with_children() {
+    let mut container = get_features(300, 200, 0.02, 1.0);
+    container.child_count = 4;
+    assert_kind(&container, element_kind::Container);
+    // Empty
+    assert_not_kind(&get_features(300, 200, 0.02, 1.0), element_kind::Container);
+    // Solid
+    container.fill_ratio = 0.9;
+    assert_not_kind(&container, element_kind::Container);
+}
+
+#[test]
+fn nothing_fitting_is_guessed_by_size() {
+    // Large, solid and only two colours, so no kind fits
+    assert_eq!(classify(&get_features(100, 100, 0.9, 1.0)), (element_kind::Container, 0.0));
+    // A speck
+    assert_eq!(classify(&get_features(5, 5, 0.2, 1.0)), (element_kind::Icon, 0.0));
+}
+
+// Pixels of a circle outline in a square with its top left at (x, y)
+fn get_ring(x: u32, y: u32, radius: f32) -> line {
+    let mut pixels: Vec<(u32, u32)> = Vec::new();
+    let size = (2.0 * radius) as u32 + 1;
+    for ring_y in 0..size {
+        for ring_x in 0..size {
+            let distance = ((ring_x as f32 - radius).powi(2) + (ring_y as f32 - radius).powi(2)).sqrt();
+            if (distance - radius).abs() < 0.75 {
+                pixels.push((x + ring_x, y + ring_y));
+            }
+        }
+    }
+    return get_line(pixels);
+}
+
+#[test]
+fn features_of_lines() {
+    let lines = vec![get_outline(0, 0, 100, 30), get_outline(10, 8, 40, 12), get_ring(120, 0, 8.0)];
+    let features = get_elements_features(&lines, &[2, 2, 2], &[false, true, false]);
+    assert_eq!((features[0].width, features[0].height), (100, 30));
+    assert_eq!(features[0].corner_fill, 1.0);
+    assert_eq!((features[0].child_count, features[0].text_child_count, features[0].parent_count), (1, 1, 0));
+    assert!((features[0].fill_ratio - 256.0 / 3000.0).abs() < 1e-6);
+    assert_eq!((features[1].child_count, features[1].parent_count), (0, 1));
+    assert!(features[1].is_text);
+    assert_eq!(features[2].corner_fill, 0.0);
+    assert_eq!((features[2].child_count, features[2].parent_count), (0, 0));
+    assert_eq!(classify(&features[0]).0, element_kind::Button);
+    assert_eq!(classify(&features[2]).0, element_kind::RadioButton);
+}
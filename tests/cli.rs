@@ -0,0 +1,153 @@
+mod common;
+
+use std::process::{Command, Output};
+use common::{get_box_image, temp_directory};
+
+// A fresh directory holding a screenshot with one dark box on it
+fn get_directory(name: &str) -> temp_directory {
+    let directory = temp_directory::new(&format!("cli_{}", name));
+    get_box_image().save(directory.join("screenshot.png")).unwrap();
+    return directory;
+}
+
+fn run(directory: &temp_directory, args: &[&str]) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_NEA-element-detection"))
+        .current_dir(directory.path())
+        .args(args)
+        .output()
+        .unwrap();
+}
+
+// Elements of the only image printed by --format json
+fn get_elements(output: &Output) -> Vec<serde_json::Value> {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    return document[0]["elements"].as_array().unwrap().clone();
+}
+
+fn has_rotated_bounds(elements: &[serde_json::Value]) -> bool {
+    assert!(!elements.is_empty());
+    return elements.iter().all(|element| !element["rotated_bounds"].is_null() && !element["hull"].is_null());
+}
+
+#[test]
+fn bool_flags_can_turn_off_what_a_config_turns_on() {
+    let directory = get_directory("bool_flags");
+    std::fs::write(directory.join("config.toml"), "rotated_bounds = true\n").unwrap();
+    let args = ["screenshot.png", "--format", "json", "--ocr", "none"];
+    let plain = get_elements(&run(&directory, &args));
+    let flag = get_elements(&run(&directory, &[&args[..], &["--rotated-bounds"]].concat()));
+    let configured = get_elements(&run(&directory, &[&args[..], &["--config", "config.toml"]].concat()));
+    let turned_off = get_elements(&run(&directory, &[&args[..], &["--config", "config.toml", "--rotated-bounds=false"]].concat()));
+    let turned_on = get_elements(&run(&directory, &[&args[..], &["--rotated-bounds=true"]].concat()));
+    assert!(plain.iter().all(|element| element["rotated_bounds"].is_null()));
+    assert!(has_rotated_bounds(&flag));
+    assert!(has_rotated_bounds(&configured));
+    assert!(turned_off.iter().all(|element| element["rotated_bounds"].is_null()));
+    assert!(has_rotated_bounds(&turned_on));
+}
+
+#[test]
+fn bool_flags_dont_take_the_next_argument() {
+    // The image after the flag is still an input, not the flag's value
+    let directory = get_directory("bool_inputs");
+    let output = run(&directory, &["--suppress-duplicates", "screenshot.png", "--format", "json", "--ocr", "none"]);
+    let bad_value = run(&directory, &["screenshot.png", "--normalise-activation=maybe"]);
+    assert!(!get_elements(&output).is_empty());
+    assert!(!bad_value.status.success());
+}
+
+#[test]
+fn inputs_are_required_and_must_match() {
+    let directory = get_directory("inputs");
+    let no_inputs = run(&directory, &[]);
+    let no_matches = run(&directory, &["missing_*.png"]);
+    let bad_threshold = run(&directory, &["screenshot.png", "--line-threshold", "bright"]);
+    assert!(!no_inputs.status.success());
+    assert!(String::from_utf8_lossy(&no_inputs.stderr).contains("<INPUTS>"));
+    assert!(!no_matches.status.success());
+    assert!(String::from_utf8_lossy(&no_matches.stderr).contains("no files match \"missing_*.png\""));
+    assert!(!bad_threshold.status.success());
+}
+
+#[test]
+fn globbed_images_are_drawn_to_the_output_directory() {
+    let directory = get_directory("output_dir");
+    std::fs::copy(directory.join("screenshot.png"), directory.join("screenshot_2.png")).unwrap();
+    let output = run(&directory, &["screenshot*.png", "-o", "lines", "--ocr", "none", "--line-threshold", "20"]);
+    let written: Vec<bool> = ["lines/line_screenshot.png", "lines/line_screenshot_2.png"].iter()
+        .map(|path| directory.join(path).is_file())
+        .collect();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(written, vec![true, true]);
+    // Images are written rather than printed
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn unreadable_images_are_skipped_and_reported() {
+    let directory = get_directory("unreadable");
+    std::fs::write(directory.join("screenshot_corrupt.png"), b"not a png").unwrap();
+    std::fs::copy(directory.join("screenshot.png"), directory.join("screenshot_2.png")).unwrap();
+    let output = run(&directory, &["screenshot*.png", "--format", "json", "--ocr", "none"]);
+    // The other images are still printed, but the run fails
+    assert!(!output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let paths: Vec<&str> = document.as_array().unwrap().iter().map(|image| image["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, vec!["screenshot.png", "screenshot_2.png"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error: screenshot_corrupt.png:"), "{}", stderr);
+    assert!(stderr.contains("Error: 1 of 3 images failed"), "{}", stderr);
+}
+
+#[test]
+fn json_output_creates_no_directories() {
+    let directory = get_directory("json_output_dir");
+    let output = run(&directory, &["screenshot.png", "-o", "lines", "--format", "json", "--ocr", "none"]);
+    let created = directory.join("lines").exists();
+    assert_eq!(get_elements(&output).len(), 1);
+    assert!(!created);
+}
+
+#[test]
+fn layouts_are_only_found_for_json() {
+    let directory = get_directory("layouts");
+    let json = run(&directory, &["screenshot.png", "--format", "json", "--ocr", "none", "--layouts"]);
+    let image = run(&directory, &["screenshot.png", "--ocr", "none", "--layouts"]);
+    assert!(json.status.success(), "{}", String::from_utf8_lossy(&json.stderr));
+    let document: serde_json::Value = serde_json::from_slice(&json.stdout).unwrap();
+    assert!(document[0]["layouts"].is_array());
+    assert!(image.status.success(), "{}", String::from_utf8_lossy(&image.stderr));
+    assert!(directory.join("line_screenshot.png").is_file());
+}
+
+#[test]
+fn eval_and_tune_reject_iou_thresholds_outside_0_to_1() {
+    let directory = get_directory("iou_threshold");
+    let mut outputs: Vec<Output> = Vec::new();
+    for command in ["eval", "tune"] {
+        for iou_threshold in ["0", "-0.5", "1.5", "nan"] {
+            let arg = format!("--iou-threshold={}", iou_threshold);
+            outputs.push(run(&directory, &[command, "ground_truth.json", &arg]));
+        }
+    }
+    for output in outputs {
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("for '--iou-threshold <IOU_THRESHOLD>'"), "{}", stderr);
+        assert!(stderr.contains("expected above 0 and at most 1"), "{}", stderr);
+    }
+}
+
+#[test]
+fn tune_rejects_grids_too_large_to_search() {
+    let directory = get_directory("grid_steps");
+    let too_many = run(&directory, &["tune", "ground_truth.json", "--strategy", "grid", "--grid-steps", "6"]);
+    let out_of_range = run(&directory, &["tune", "ground_truth.json", "--strategy", "grid", "--grid-steps", "0"]);
+    assert!(!too_many.status.success());
+    let stderr = String::from_utf8_lossy(&too_many.stderr);
+    assert!(stderr.contains("--grid-steps 6 would try 46656 settings, more than 10000"), "{}", stderr);
+    assert!(!out_of_range.status.success());
+    let stderr = String::from_utf8_lossy(&out_of_range.stderr);
+    assert!(stderr.contains("for '--grid-steps <GRID_STEPS>'"), "{}", stderr);
+}
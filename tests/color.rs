@@ -0,0 +1,105 @@
+use image::{ImageBuffer, Rgb};
+use nea_element_detection::activation::{brush_difference_filter, IMMEDIATE_NEIGHBOUR_WEIGHT};
+use nea_element_detection::color::{
+    color_difference, get_color_difference, get_delta_e2000, perceptual_difference_filter, rgb_to_lab,
+    rgb_to_oklab, COLOR_DIFFERENCES
+};
+
+fn assert_close(found: [f32; 3], expected: [f32; 3], tolerance: f32) {
+    assert!(
+        found.iter().zip(expected.iter()).all(|(a, b)| (a - b).abs() < tolerance),
+        "{:?} != {:?}", found, expected
+    );
+}
+
+#[test]
+fn delta_e2000_matches_reference_pairs() {
+    // From Sharma, Wu and Dalal, "The CIEDE2000 color-difference formula: implementation
+    // notes, supplementary test data, and mathematical observations" (2005), table 1.
+    // Includes pairs either side of the hue angle discontinuity.
+    let pairs: [([f32; 3], [f32; 3], f32); 14] = [
+        ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+        ([50.0, 3.1571, -77.2803], [50.0, 0.0, -82.7485], 2.8615),
+        ([50.0, 2.8361, -74.0200], [50.0, 0.0, -82.7485], 3.4412),
+        ([50.0, -1.3802, -84.2814], [50.0, 0.0, -82.7485], 1.0000),
+        ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+        ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0009], 7.1792),
+        ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0010], 7.1792),
+        ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0011], 7.2195),
+        ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+        ([60.2574, -34.0099, 36.2677], [60.4626, -34.1751, 39.4387], 1.2644),
+        ([63.0109, -31.0961, -5.8663], [62.8187, -29.7946, -4.0864], 1.2630),
+        ([35.0831, -44.1164, 3.7933], [35.0232, -40.0716, 1.5901], 1.8645),
+        ([22.7233, 20.0904, -46.6940], [23.0331, 14.9730, -42.5619], 2.0373),
+        ([90.8027, -2.0831, 1.4410], [91.1528, -1.6435, 0.0447], 1.4441),
+    ];
+    for (lab_1, lab_2, expected) in pairs {
+        for (a, b) in [(lab_1, lab_2), (lab_2, lab_1)] {
+            let found = get_delta_e2000(&a, &b);
+            assert!((found - expected).abs() < 1e-3, "{:?} {:?}: {} != {}", a, b, found, expected);
+        }
+    }
+    assert_eq!(get_delta_e2000(&[50.0, 2.5, 0.0], &[50.0, 2.5, 0.0]), 0.0);
+}
+
+#[test]
+fn lab_of_primaries() {
+    assert_close(rgb_to_lab(&Rgb([0, 0, 0])), [0.0, 0.0, 0.0], 1e-3);
+    assert_close(rgb_to_lab(&Rgb([255, 255, 255])), [100.0, 0.0, 0.0], 1e-2);
+    assert_close(rgb_to_lab(&Rgb([255, 0, 0])), [53.2408, 80.0925, 67.2032], 2e-2);
+    assert_close(rgb_to_lab(&Rgb([0, 0, 255])), [32.2970, 79.1875, -107.8602], 2e-2);
+    // Greys have no colour
+    let grey = rgb_to_lab(&Rgb([119, 119, 119]));
+    assert!(grey[1].abs() < 1e-2 && grey[2].abs() < 1e-2, "{:?}", grey);
+}
+
+#[test]
+fn oklab_of_primaries() {
+    // Reference values from Ottosson's OKLab post
+    assert_close(rgb_to_oklab(&Rgb([0, 0, 0])), [0.0, 0.0, 0.0], 1e-4);
+    assert_close(rgb_to_oklab(&Rgb([255, 255, 255])), [1.0, 0.0, 0.0], 1e-3);
+    assert_close(rgb_to_oklab(&Rgb([255, 0, 0])), [0.62796, 0.22486, 0.12585], 1e-3);
+    assert_close(rgb_to_oklab(&Rgb([0, 255, 0])), [0.86644, -0.23389, 0.17950], 1e-3);
+    assert_close(rgb_to_oklab(&Rgb([0, 0, 255])), [0.45201, -0.03246, -0.31153], 1e-3);
+}
+
+#[test]
+fn black_to_white_is_255() {
+    for color_difference in COLOR_DIFFERENCES {
+        let difference = get_color_difference(&Rgb([0, 0, 0]), &Rgb([255, 255, 255]), color_difference);
+        let expected = if color_difference == color_difference::Rgb { 255.0 * 3.0_f32.sqrt() } else { 255.0 };
+        assert!((difference - expected).abs() < 0.5, "{}: {}", color_difference, difference);
+        assert_eq!(get_color_difference(&Rgb([40, 90, 200]), &Rgb([40, 90, 200]), color_difference), 0.0);
+    }
+}
+
+#[test]
+fn max_difference_is_never_exceeded() {
+    let pairs = [
+        (Rgb([0, 0, 0]), Rgb([255, 255, 255])),
+        (Rgb([0, 0, 255]), Rgb([0, 255, 0])),
+        (Rgb([0, 0, 255]), Rgb([255, 255, 0])),
+        (Rgb([0, 0, 110]), Rgb([143, 255, 0])),
+        (Rgb([255, 0, 255]), Rgb([0, 255, 0])),
+    ];
+    for color_difference in COLOR_DIFFERENCES {
+        let max_difference = color_difference.get_max_difference();
+        let largest = pairs.iter()
+            .map(|(a, b)| get_color_difference(a, b, color_difference))
+            .fold(0.0, f32::max);
+        assert!(largest <= max_difference, "{}: {} > {}", color_difference, largest, max_difference);
+        // The bound is close, not just safe
+        assert!(largest > max_difference - 2.0, "{}: {} < {}", color_difference, largest, max_difference);
+    }
+}
+
+#[test]
+fn perceptual_filter_with_rgb_matches_brush_filter() {
+    let image = ImageBuffer::from_fn(19, 13, |x, y| {
+        Rgb([((x * 53 + y * 17) % 256) as u8, ((x * 7 + y * 91) % 256) as u8, ((x * y * 29) % 256) as u8])
+    });
+    let expected = brush_difference_filter(&image, IMMEDIATE_NEIGHBOUR_WEIGHT);
+    let found = perceptual_difference_filter(&image, IMMEDIATE_NEIGHBOUR_WEIGHT, color_difference::Rgb);
+    let is_same = found.values().iter().zip(expected.values()).all(|(a, b)| a.to_bits() == b.to_bits());
+    assert!(is_same);
+}
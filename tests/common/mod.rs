@@ -0,0 +1,70 @@
+// Fixtures shared by the integration tests. Each test crate uses only some of them.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use image::{Rgb, RgbImage};
+use nea_element_detection::lines::{get_lines_stats, line};
+
+// A fresh directory under the system temp directory, removed when dropped so a failing
+// assertion doesn't leave it behind
+pub struct temp_directory {
+    path: PathBuf,
+}
+
+impl temp_directory {
+    // name keeps tests running at the same time apart
+    pub fn new(name: &str) -> temp_directory {
+        let path = std::env::temp_dir().join(format!("nea_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        return temp_directory { path };
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        return self.path.join(path);
+    }
+}
+
+impl Drop for temp_directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+// An 80x60 light screenshot with a dark 30x20 box on it at (20, 20)
+pub fn get_box_image() -> RgbImage {
+    let mut image = RgbImage::from_pixel(80, 60, Rgb([250, 250, 250]));
+    for y in 20..40 {
+        for x in 20..50 {
+            image.put_pixel(x, y, Rgb([30, 30, 30]));
+        }
+    }
+    return image;
+}
+
+// A line made of exactly these pixels
+pub fn get_line(pixels: Vec<(u32, u32)>) -> line {
+    return get_lines_stats(vec![pixels]).remove(0);
+}
+
+// Pixels of a box outline, one pixel thick
+pub fn get_outline_pixels(x: u32, y: u32, width: u32, height: u32) -> Vec<(u32, u32)> {
+    let (right, bottom) = (x + width - 1, y + height - 1);
+    let mut pixels: Vec<(u32, u32)> = Vec::new();
+    for outline_x in x..=right {
+        pixels.push((outline_x, y));
+        pixels.push((outline_x, bottom));
+    }
+    for outline_y in y + 1..bottom {
+        pixels.push((x, outline_y));
+        pixels.push((right, outline_y));
+    }
+    return pixels;
+}
+
+pub fn get_outline(x: u32, y: u32, width: u32, height: u32) -> line {
+    return get_line(get_outline_pixels(x, y, width, height));
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use image::{Rgb, RgbImage};
+use nea_element_detection::color::color_difference;
+use nea_element_detection::edges::edge_operator;
+use nea_element_detection::pipeline::config_error;
+use nea_element_detection::threshold::threshold_mode;
+use nea_element_detection::{detector, detector_config, rect};
+use common::{get_box_image, temp_directory};
+
+#[test]
+fn default_detector_finds_a_box() {
+    let detector = detector::new(detector_config::default());
+    assert_eq!(detector.config(), &detector_config::default());
+    let elements = detector.try_detect(&get_box_image()).unwrap();
+    assert_eq!(elements.len(), 1);
+    let element = &elements[0];
+    // The brush activates two pixels either side of each edge
+    assert_eq!(element.line.bounds, rect::new(18, 18, 34, 24));
+    assert_eq!((element.parent, element.children.len()), (None, 0));
+    assert!((0.0..=1.0).contains(&element.confidence));
+    assert!(detector.detect(&RgbImage::from_pixel(80, 60, Rgb([250, 250, 250]))).is_empty());
+}
+
+#[test]
+fn validate_rejects_out_of_range_settings() {
+    assert!(detector_config::default().validate().is_ok());
+    let invalid = [
+        ("line_threshold", detector_config { line_threshold: -1.0, ..detector_config::default() }),
+        ("threshold_percentile", detector_config { threshold_percentile: 101.0, ..detector_config::default() }),
+        ("hysteresis_ratio", detector_config { hysteresis_ratio: 0.0, ..detector_config::default() }),
+        ("canny_low_threshold", detector_config { canny_low_threshold: 0.0, ..detector_config::default() }),
+        ("area_threshold", detector_config { area_threshold: 0, ..detector_config::default() }),
+        ("pyramid_levels", detector_config { pyramid_levels: 9, ..detector_config::default() }),
+        ("pyramid_scale", detector_config { pyramid_scale: 1.0, ..detector_config::default() }),
+        ("duplicate_iou_threshold", detector_config { duplicate_iou_threshold: f32::NAN, ..detector_config::default() }),
+    ];
+    for (name, config) in invalid {
+        let result = config.validate();
+        assert!(matches!(&result, Err(config_error::OutOfRange(message)) if message.starts_with(name)), "{:?}", result);
+    }
+
+    // Blue to yellow is further apart in CIELAB than black to white is in RGB
+    let cie76 = detector_config {
+        color_difference: color_difference::Cie76,
+        difference_color_threshold: 600.0,
+        ..detector_config::default()
+    };
+    let rgb = detector_config { color_difference: color_difference::Rgb, ..cie76.clone() };
+    let oklab = detector_config { color_difference: color_difference::Oklab, difference_color_threshold: 300.0, ..cie76.clone() };
+    assert!(cie76.validate().is_ok());
+    assert!(matches!(rgb.validate(), Err(config_error::OutOfRange(_))));
+    assert!(matches!(oklab.validate(), Err(config_error::OutOfRange(_))));
+}
+
+#[test]
+fn configs_save_and_load_as_toml_and_json() {
+    let directory = temp_directory::new("detector_round_trip");
+    let config = detector_config {
+        threshold_mode: threshold_mode::Percentile,
+        edge_operator: edge_operator::Canny,
+        area_threshold: 12,
+        rotated_bounds: true,
+        ..detector_config::default()
+    };
+    let mut loaded: Vec<detector_config> = Vec::new();
+    for name in ["config.toml", "config.json"] {
+        let path = directory.join(name);
+        config.save(&path).unwrap();
+        loaded.push(detector_config::load(&path).unwrap());
+    }
+    let missing = detector_config::load(&directory.join("missing.toml"));
+
+    assert_eq!(loaded, vec![config.clone(), config]);
+    assert!(matches!(missing, Err(config_error::Read(_))));
+}
+
+#[test]
+fn config_round_trips_through_json() {
+    let config = detector_config { rotated_bounds: true, pyramid_levels: 3, ..detector_config::default() };
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(serde_json::from_str::<detector_config>(&json).unwrap(), config);
+    // Settings left out keep their defaults
+    assert_eq!(serde_json::from_str::<detector_config>("{}").unwrap(), detector_config::default());
+}
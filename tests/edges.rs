@@ -0,0 +1,70 @@
+use image::{Rgb, RgbImage};
+use nea_element_detection::activation::activation_map;
+use nea_element_detection::color::color_difference;
+use nea_element_detection::edges::{
+    edge_filter, edge_operator, edge_settings, get_canny_activations, BLUR_SIGMA, CANNY_HIGH_THRESHOLD,
+    CANNY_LOW_THRESHOLD
+};
+
+const SIZE: u32 = 24;
+const STEP_X: u32 = SIZE / 2; // First white column
+
+// Black on the left, white from STEP_X on
+fn get_step_image() -> RgbImage {
+    return RgbImage::from_fn(SIZE, SIZE, |x, _| if x < STEP_X { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) });
+}
+
+fn get_settings() -> edge_settings {
+    return edge_settings {
+        immediate_neighbour_weight: 0.6,
+        color_difference: color_difference::Rgb,
+        blur_sigma: BLUR_SIGMA,
+        canny_low_threshold: CANNY_LOW_THRESHOLD,
+        canny_high_threshold: CANNY_HIGH_THRESHOLD,
+    };
+}
+
+// Largest activation in each column
+fn get_column_maxima(map: &activation_map) -> Vec<f32> {
+    return (0..map.width())
+        .map(|x| (0..map.height()).map(|y| map.get(x, y)).fold(0.0, f32::max))
+        .collect();
+}
+
+#[test]
+fn operators_find_a_step() {
+    let image = get_step_image();
+    for operator in [edge_operator::Sobel, edge_operator::Scharr, edge_operator::LaplacianOfGaussian, edge_operator::Canny] {
+        let maxima = get_column_maxima(&edge_filter(&image, operator, &get_settings()));
+        // Strong next to the step, on the scale of the step's height
+        let strongest = maxima[STEP_X as usize - 1].max(maxima[STEP_X as usize]);
+        assert!(strongest > 100.0 && strongest < 400.0, "{} gave {}", operator, strongest);
+        // Nothing away from it
+        for (x, maximum) in maxima.iter().enumerate() {
+            if x.abs_diff(STEP_X as usize) > 4 {
+                assert_eq!(*maximum, 0.0, "{} at column {}", operator, x);
+            }
+        }
+        // The same down the whole edge
+        let column = if maxima[STEP_X as usize - 1] >= maxima[STEP_X as usize] { STEP_X - 1 } else { STEP_X };
+        let map = edge_filter(&image, operator, &get_settings());
+        assert!((0..SIZE).all(|y| map.get(column, y) == strongest), "{}", operator);
+    }
+}
+
+#[test]
+fn operators_find_nothing_in_flat_images() {
+    let image = RgbImage::from_pixel(SIZE, SIZE, Rgb([90, 140, 200]));
+    for operator in [edge_operator::Sobel, edge_operator::Scharr, edge_operator::LaplacianOfGaussian, edge_operator::Canny] {
+        let map = edge_filter(&image, operator, &get_settings());
+        assert!(map.values().iter().all(|value| *value == 0.0), "{}", operator);
+    }
+}
+
+#[test]
+fn canny_finishes_with_a_low_threshold_of_zero() {
+    // Every pixel passes the low threshold, including those with no gradient at all
+    let activations = get_canny_activations(&get_step_image(), BLUR_SIGMA, 0.0, CANNY_HIGH_THRESHOLD);
+    let with_default = get_canny_activations(&get_step_image(), BLUR_SIGMA, CANNY_LOW_THRESHOLD, CANNY_HIGH_THRESHOLD);
+    assert_eq!(get_column_maxima(&activations), get_column_maxima(&with_default));
+}
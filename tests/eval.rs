@@ -0,0 +1,108 @@
+mod common;
+
+use std::path::PathBuf;
+use nea_element_detection::eval::{dataset_error, detection, evaluate, labelled_image, load_dataset};
+use nea_element_detection::synthetic::ground_truth_element;
+use nea_element_detection::{element_kind, rect};
+use common::temp_directory;
+
+fn get_element(kind: element_kind, bounds: rect) -> ground_truth_element {
+    return ground_truth_element { kind, bounds, text: None };
+}
+
+fn get_detection(kind: element_kind, bounds: rect, confidence: f32) -> detection {
+    return detection { kind, bounds, confidence };
+}
+
+fn get_dataset() -> Vec<labelled_image> {
+    return vec![labelled_image {
+        path: PathBuf::from("screen.png"),
+        elements: vec![
+            get_element(element_kind::Button, rect::new(10, 10, 80, 30)),
+            get_element(element_kind::Button, rect::new(10, 60, 80, 30)),
+            get_element(element_kind::TextLabel, rect::new(20, 20, 40, 10)),
+        ],
+    }];
+}
+
+#[test]
+fn perfect_detections_score_one() {
+    let detections = vec![vec![
+        get_detection(element_kind::Button, rect::new(10, 10, 80, 30), 0.9),
+        get_detection(element_kind::Button, rect::new(11, 60, 80, 30), 0.8),
+        get_detection(element_kind::TextLabel, rect::new(20, 20, 40, 10), 0.7),
+    ]];
+    let report = evaluate(&get_dataset(), &detections, 0.5);
+    assert_eq!((report.precision, report.recall, report.f1), (1.0, 1.0, 1.0));
+    assert_eq!(report.mean_average_precision, 1.0);
+    assert_eq!(report.kinds.len(), 2);
+}
+
+#[test]
+fn misses_wrong_kinds_and_low_overlap_count_against() {
+    let detections = vec![vec![
+        get_detection(element_kind::Button, rect::new(10, 10, 80, 30), 0.9),
+        // Right place, wrong kind
+        get_detection(element_kind::TextField, rect::new(10, 60, 80, 30), 0.8),
+        // Too little overlap
+        get_detection(element_kind::TextLabel, rect::new(40, 20, 40, 10), 0.7),
+    ]];
+    let report = evaluate(&get_dataset(), &detections, 0.5);
+    assert_eq!(report.overall.true_positives, 1);
+    assert_eq!(report.overall.false_positives, 2);
+    assert_eq!(report.overall.false_negatives, 2);
+    let button = report.kinds.iter().find(|kind| kind.kind == element_kind::Button).unwrap();
+    assert_eq!((button.precision, button.recall), (1.0, 0.5));
+    assert_eq!(button.average_precision, Some(0.5));
+    // Detected but not labelled, so has no average precision and isn't in the mean
+    let text_field = report.kinds.iter().find(|kind| kind.kind == element_kind::TextField).unwrap();
+    assert_eq!(text_field.average_precision, None);
+    assert_eq!(report.mean_average_precision, 0.25);
+    assert_eq!(report.images[0].counts, report.overall);
+}
+
+#[test]
+fn confident_false_positives_lower_average_precision() {
+    let detections = vec![vec![
+        get_detection(element_kind::Button, rect::new(200, 200, 80, 30), 0.95),
+        get_detection(element_kind::Button, rect::new(10, 10, 80, 30), 0.9),
+        get_detection(element_kind::Button, rect::new(10, 60, 80, 30), 0.8),
+    ]];
+    let report = evaluate(&get_dataset(), &detections, 0.5);
+    let button = report.kinds.iter().find(|kind| kind.kind == element_kind::Button).unwrap();
+    // Recall 0.5 at precision 1/2, then 1.0 at 2/3, interpolated to 2/3 for both
+    assert!((button.average_precision.unwrap() - 2.0 / 3.0).abs() < 1e-6);
+}
+
+#[test]
+fn loads_coco() {
+    let directory = temp_directory::new("eval_coco");
+    let path = directory.join("annotations.json");
+    std::fs::write(&path, r#"{
+        "images": [{"id": 1, "file_name": "a.png", "width": 100, "height": 100}],
+        "annotations": [
+            {"id": 1, "image_id": 1, "category_id": 3, "bbox": [10, 20, 30.4, 15]},
+            {"id": 2, "image_id": 1, "category_id": 4, "bbox": [50, 50, 10, 10]}
+        ],
+        "categories": [{"id": 3, "name": "text_field"}, {"id": 4, "name": "radio button"}]
+    }"#).unwrap();
+    let dataset = load_dataset(&path).unwrap();
+    assert_eq!(dataset.len(), 1);
+    assert_eq!(dataset[0].path, directory.join("a.png"));
+    assert_eq!(dataset[0].elements, vec![
+        get_element(element_kind::TextField, rect::new(10, 20, 30, 15)),
+        get_element(element_kind::RadioButton, rect::new(50, 50, 10, 10)),
+    ]);
+}
+
+#[test]
+fn rejects_synthetic_ground_truth_with_zero_sizes() {
+    let directory = temp_directory::new("eval_synthetic");
+    let path = directory.join("ground_truth.json");
+    std::fs::write(&path, r#"{
+        "path": "a.png", "width": 100, "height": 100,
+        "elements": [{"kind": "button", "bounds": {"x": 10, "y": 20, "width": 0, "height": 0}, "text": null}]
+    }"#).unwrap();
+    let dataset = load_dataset(&path);
+    assert!(matches!(&dataset, Err(dataset_error::Invalid(message)) if message.contains("0x0")), "{:?}", dataset);
+}
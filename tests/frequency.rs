@@ -0,0 +1,102 @@
+mod common;
+
+use image::{Rgb, RgbImage};
+use num_complex::Complex32;
+use nea_element_detection::frequency::{
+    detect_layout, fft, get_high_frequency_share, get_region_spectrum, layout_kind
+};
+use nea_element_detection::{detector, detector_config, rect};
+use common::get_box_image;
+
+fn get_whole_image(buffer: &RgbImage) -> rect {
+    return rect::new(0, 0, buffer.width(), buffer.height());
+}
+
+// Rows 40px tall, each with a separator along the bottom and an icon on the left
+fn get_list_image() -> RgbImage {
+    return RgbImage::from_fn(300, 400, |x, y| {
+        if y % 40 == 39 {
+            return Rgb([200, 200, 200]);
+        }
+        if (13..26).contains(&(y % 40)) && (20..40).contains(&x) {
+            return Rgb([50, 50, 200]);
+        }
+        return Rgb([255, 255, 255]);
+    });
+}
+
+// 40px tiles every 60px both ways
+fn get_grid_image() -> RgbImage {
+    return RgbImage::from_fn(300, 300, |x, y| {
+        if (11..50).contains(&(x % 60)) && (11..50).contains(&(y % 60)) {
+            return Rgb([90, 90, 90]);
+        }
+        return Rgb([255, 255, 255]);
+    });
+}
+
+fn get_gradient_image() -> RgbImage {
+    return RgbImage::from_fn(300, 300, |x, y| Rgb([(x / 2) as u8, (y / 2) as u8, 100]));
+}
+
+#[test]
+fn fft_matches_dft_and_inverts() {
+    let values: Vec<Complex32> = (0..16).map(|i| Complex32::new((i * i % 7) as f32, (i % 3) as f32)).collect();
+    let mut transformed = values.clone();
+    fft(&mut transformed, false);
+    for (k, coefficient) in transformed.iter().enumerate() {
+        let expected: Complex32 = values.iter().enumerate()
+            .map(|(n, value)| value * Complex32::from_polar(1.0, -2.0 * std::f32::consts::PI * (k * n) as f32 / 16.0))
+            .sum();
+        assert!((coefficient - expected).norm() < 1e-3, "{}: {} != {}", k, coefficient, expected);
+    }
+    fft(&mut transformed, true);
+    for (value, original) in transformed.iter().zip(&values) {
+        assert!((value - original).norm() < 1e-4);
+    }
+}
+
+#[test]
+fn finds_list_rows() {
+    let buffer = get_list_image();
+    let layout = detect_layout(&buffer, &get_whole_image(&buffer)).unwrap();
+    assert_eq!(layout.kind, layout_kind::List);
+    assert_eq!(layout.row_height, Some(40.0));
+}
+
+#[test]
+fn finds_grid_cells() {
+    let buffer = get_grid_image();
+    let layout = detect_layout(&buffer, &get_whole_image(&buffer)).unwrap();
+    assert_eq!(layout.kind, layout_kind::Grid);
+    assert_eq!((layout.column_width, layout.row_height), (Some(60.0), Some(60.0)));
+}
+
+#[test]
+fn smooth_images_have_no_layout_or_high_frequencies() {
+    let buffer = get_gradient_image();
+    let region = get_whole_image(&buffer);
+    assert_eq!(detect_layout(&buffer, &region), None);
+    assert!(get_high_frequency_share(&get_region_spectrum(&buffer, &region)) < 0.05);
+    // Alternating columns are all high frequency
+    let stripes = RgbImage::from_fn(64, 64, |x, _| if x % 2 == 0 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) });
+    assert!(get_high_frequency_share(&get_region_spectrum(&stripes, &get_whole_image(&stripes))) > 0.9);
+}
+
+#[test]
+fn high_frequency_share_rules_out_smooth_two_colour_lines() {
+    // A soft edged card, with a colour threshold loose enough to take its blends as one of
+    // its two colours, passes as text on colour alone
+    let buffer = image::imageops::blur(&get_box_image(), 3.0);
+    let is_text = |text_high_frequency_share: f32| -> Vec<bool> {
+        let config = detector_config {
+            difference_color_threshold: 180.0,
+            text_high_frequency_share,
+            ..detector_config::default()
+        };
+        let elements = detector::new(config).with_recognizer(None).detect(&buffer);
+        return elements.iter().map(|element| element.text.is_some()).collect();
+    };
+    assert_eq!(is_text(0.0), vec![true]);
+    assert_eq!(is_text(0.1), vec![false]);
+}
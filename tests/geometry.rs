@@ -0,0 +1,82 @@
+use std::f32::consts::FRAC_PI_4;
+use nea_element_detection::geometry::{get_convex_hull, get_min_area_rect, rotated_rect};
+
+fn assert_rect(rect: rotated_rect, center: (f32, f32), width: f32, height: f32, angle: f32) {
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+    assert!(
+        close(rect.center.0, center.0) && close(rect.center.1, center.1)
+            && close(rect.width, width) && close(rect.height, height) && close(rect.angle, angle),
+        "{:?}", rect
+    );
+}
+
+// No three consecutive hull points on one line, and every turn the same way
+fn assert_strictly_convex(hull: &[(u32, u32)]) {
+    for i in 0..hull.len() {
+        let (a, b, c) = (hull[i], hull[(i + 1) % hull.len()], hull[(i + 2) % hull.len()]);
+        let cross = (b.0 as i64 - a.0 as i64) * (c.1 as i64 - a.1 as i64)
+            - (b.1 as i64 - a.1 as i64) * (c.0 as i64 - a.0 as i64);
+        assert!(cross > 0, "{:?}", hull);
+    }
+}
+
+#[test]
+fn single_pixel() {
+    let hull = get_convex_hull(&[(3, 4)]);
+    assert_eq!(hull.len(), 4);
+    assert_strictly_convex(&hull);
+    assert_rect(get_min_area_rect(&hull).unwrap(), (3.5, 4.5), 1.0, 1.0, 0.0);
+    assert!(get_convex_hull(&[]).is_empty());
+    assert_eq!(get_min_area_rect(&[]), None);
+}
+
+#[test]
+fn collinear_pixels() {
+    // A row of pixels, so the hull is the corners of the row and nothing between them
+    let row: Vec<(u32, u32)> = (2..9).map(|x| (x, 5)).collect();
+    let hull = get_convex_hull(&row);
+    assert_eq!(hull.len(), 4);
+    assert_strictly_convex(&hull);
+    assert_rect(get_min_area_rect(&hull).unwrap(), (5.5, 5.5), 7.0, 1.0, 0.0);
+
+    // A diagonal, fitted by a box along it
+    let diagonal: Vec<(u32, u32)> = (0..20).map(|i| (i, i)).collect();
+    let hull = get_convex_hull(&diagonal);
+    assert_strictly_convex(&hull);
+    let rect = get_min_area_rect(&hull).unwrap();
+    assert!((rect.angle.abs() - FRAC_PI_4).abs() < 1e-4, "{:?}", rect);
+    assert!(rect.area() < 20.0 * 20.0 / 4.0, "{:?}", rect);
+
+    // Collinear points given straight to get_min_area_rect have no area
+    let rect = get_min_area_rect(&[(0, 0), (5, 0), (10, 0)]).unwrap();
+    assert_eq!(rect.area(), 0.0);
+    assert!(rect.center.0.is_finite() && rect.center.1.is_finite());
+}
+
+#[test]
+fn axis_aligned_rectangle() {
+    let mut pixels: Vec<(u32, u32)> = Vec::new();
+    for y in 3..6 {
+        for x in 2..7 {
+            pixels.push((x, y));
+        }
+    }
+    let hull = get_convex_hull(&pixels);
+    assert_eq!(hull.len(), 4);
+    assert_strictly_convex(&hull);
+    let rect = get_min_area_rect(&hull).unwrap();
+    assert_rect(rect, (4.5, 4.5), 5.0, 3.0, 0.0);
+    let mut corners = rect.corners().map(|(x, y)| (x.round() as u32, y.round() as u32)).to_vec();
+    corners.sort();
+    assert_eq!(corners, vec![(2, 3), (2, 6), (7, 3), (7, 6)]);
+}
+
+#[test]
+fn diamond_gives_its_square() {
+    // A square turned 45 degrees, side 10 * sqrt(2)
+    let diamond = [(10, 0), (20, 10), (10, 20), (0, 10)];
+    let rect = get_min_area_rect(&diamond).unwrap();
+    let side = 200.0_f32.sqrt();
+    assert_rect(rect, (10.0, 10.0), side, side, FRAC_PI_4);
+    assert!((rect.area() - 200.0).abs() < 1e-3);
+}
@@ -0,0 +1,103 @@
+use image::Rgb;
+use nea_element_detection::color::color_difference;
+use nea_element_detection::grouping::{add_paragraphs, merge_text_lines};
+use nea_element_detection::lines::{get_lines_stats, line};
+use nea_element_detection::pipeline::element_text;
+use nea_element_detection::text::DIFFERENCE_COLOR_THRESH;
+use nea_element_detection::{element, element_kind, rect};
+
+const INK: Rgb<u8> = Rgb([20, 20, 20]);
+const BACKGROUND: Rgb<u8> = Rgb([245, 245, 245]);
+
+// A solid block standing in for a letter or word
+fn get_block(x: u32, y: u32, width: u32, height: u32) -> line {
+    let mut pixels: Vec<(u32, u32)> = Vec::new();
+    for block_y in y..y + height {
+        for block_x in x..x + width {
+            pixels.push((block_x, block_y));
+        }
+    }
+    return get_lines_stats(vec![pixels]).remove(0);
+}
+
+fn get_text_element(text: &str, line: line) -> element {
+    return element {
+        line,
+        dominant_color: BACKGROUND,
+        text: Some(element_text { stroke_color: INK, text: text.to_string(), words: Vec::new() }),
+        kind: element_kind::TextLabel,
+        confidence: 0.8,
+        parent: None,
+        children: Vec::new(),
+        label: None,
+    };
+}
+
+fn merge(lines: Vec<line>, stroke_colors: Vec<Option<Rgb<u8>>>) -> Vec<rect> {
+    let (lines, _) = merge_text_lines(lines, stroke_colors, color_difference::Rgb, DIFFERENCE_COLOR_THRESH);
+    return lines.iter().map(|line| line.bounds).collect();
+}
+
+#[test]
+fn words_on_one_baseline_merge() {
+    // A gap narrower than the text is tall, and a shorter letter sharing the baseline
+    let lines = vec![get_block(0, 10, 20, 10), get_block(27, 10, 15, 10), get_block(45, 14, 5, 6)];
+    let (merged, stroke_colors) = merge_text_lines(lines, vec![Some(INK); 3], color_difference::Rgb, DIFFERENCE_COLOR_THRESH);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].bounds, rect::new(0, 10, 50, 10));
+    assert_eq!(merged[0].pixels.len(), 200 + 150 + 30);
+    assert_eq!(stroke_colors, vec![Some(INK)]);
+}
+
+#[test]
+fn large_gaps_and_other_lines_stay_apart() {
+    let words = || vec![get_block(0, 10, 20, 10), get_block(45, 10, 20, 10)];
+    // Further apart than the text is tall, e.g. two columns
+    assert_eq!(merge(words(), vec![Some(INK); 2]).len(), 2);
+    // Close, but on different lines of text
+    assert_eq!(merge(vec![get_block(0, 10, 20, 10), get_block(25, 24, 20, 10)], vec![Some(INK); 2]).len(), 2);
+    // Close, but different colours or not text
+    let near = || vec![get_block(0, 10, 20, 10), get_block(25, 10, 20, 10)];
+    assert_eq!(merge(near(), vec![Some(INK), Some(Rgb([200, 30, 30]))]).len(), 2);
+    assert_eq!(merge(near(), vec![Some(INK), None]).len(), 2);
+    assert_eq!(merge(near(), vec![Some(INK); 2]).len(), 1);
+}
+
+#[test]
+fn stroke_colours_use_the_configured_metric() {
+    // Dark blues 30 apart in RGB, but close by eye
+    let near = || vec![get_block(0, 10, 20, 10), get_block(25, 10, 20, 10)];
+    let stroke_colors = vec![Some(Rgb([0, 0, 60])), Some(Rgb([0, 0, 90]))];
+    let (rgb, _) = merge_text_lines(near(), stroke_colors.clone(), color_difference::Rgb, 25.0);
+    let (ciede2000, _) = merge_text_lines(near(), stroke_colors, color_difference::Ciede2000, 25.0);
+    assert_eq!((rgb.len(), ciede2000.len()), (2, 1));
+}
+
+#[test]
+fn lines_of_a_paragraph_are_grouped() {
+    let mut elements = vec![
+        get_text_element("second line", get_block(2, 24, 70, 10)),
+        get_text_element("first line", get_block(0, 10, 80, 10)),
+        get_text_element("third", get_block(0, 38, 30, 10)),
+        // Too far below to be the same paragraph
+        get_text_element("footer", get_block(0, 80, 40, 10)),
+    ];
+    add_paragraphs(&mut elements, color_difference::Rgb, DIFFERENCE_COLOR_THRESH);
+    assert_eq!(elements.len(), 5);
+    let paragraph = &elements[4];
+    assert_eq!(paragraph.kind, element_kind::TextLabel);
+    assert_eq!(paragraph.line.bounds, rect::new(0, 10, 80, 38));
+    let text = paragraph.text.as_ref().unwrap();
+    assert_eq!(text.text, "first line second line third");
+    assert_eq!(text.stroke_color, INK);
+    assert_eq!(paragraph.confidence, 0.8);
+}
+
+#[test]
+fn single_lines_and_non_text_make_no_paragraph() {
+    let mut non_text = get_text_element("", get_block(0, 24, 80, 10));
+    non_text.text = None;
+    let mut elements = vec![get_text_element("alone", get_block(0, 10, 80, 10)), non_text];
+    add_paragraphs(&mut elements, color_difference::Rgb, DIFFERENCE_COLOR_THRESH);
+    assert_eq!(elements.len(), 2);
+}
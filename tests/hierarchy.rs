@@ -0,0 +1,92 @@
+mod common;
+
+use image::Rgb;
+use nea_element_detection::hierarchy::{build_hierarchy, describe_element, get_ancestors};
+use nea_element_detection::pipeline::element_text;
+use nea_element_detection::{element, element_kind};
+use common::get_outline;
+
+// An element whose line is the outline of the box, only its bounds matter here
+fn get_element(kind: element_kind, text: Option<&str>, x: u32, y: u32, width: u32, height: u32) -> element {
+    return element {
+        line: get_outline(x, y, width, height),
+        dominant_color: Rgb([255, 255, 255]),
+        text: text.map(|text| element_text { stroke_color: Rgb([0, 0, 0]), text: text.to_string(), words: Vec::new() }),
+        kind,
+        confidence: 1.0,
+        parent: None,
+        children: Vec::new(),
+        label: None,
+    };
+}
+
+// A dialog with a title and two buttons, each with a caption
+fn get_dialog() -> Vec<element> {
+    return vec![
+        get_element(element_kind::Button, None, 100, 100, 60, 30),
+        get_element(element_kind::TextLabel, Some("OK"), 20, 108, 20, 12),
+        get_element(element_kind::Container, None, 0, 0, 200, 150),
+        get_element(element_kind::TextLabel, Some("Cancel"), 110, 108, 40, 12),
+        get_element(element_kind::Button, None, 10, 100, 60, 30),
+        get_element(element_kind::TextLabel, Some("Save changes"), 10, 10, 120, 12),
+    ];
+}
+
+#[test]
+fn nested_elements_get_the_smallest_parent() {
+    let mut elements = get_dialog();
+    build_hierarchy(&mut elements);
+    let parents: Vec<Option<usize>> = elements.iter().map(|element| element.parent).collect();
+    assert_eq!(parents, vec![Some(2), Some(4), None, Some(0), Some(2), Some(2)]);
+    // Children in reading order
+    assert_eq!(elements[2].children, vec![5, 4, 0]);
+    assert_eq!(elements[4].children, vec![1]);
+    assert!(elements[1].children.is_empty());
+    assert_eq!(get_ancestors(&elements, 1), vec![4, 2]);
+
+    // Labels come from text children only, not grandchildren
+    assert_eq!(elements[4].label.as_deref(), Some("OK"));
+    assert_eq!(elements[0].label.as_deref(), Some("Cancel"));
+    assert_eq!(elements[2].label.as_deref(), Some("Save changes"));
+    assert_eq!(elements[1].label, None);
+}
+
+#[test]
+fn overlapping_siblings_are_not_nested() {
+    let mut elements = vec![
+        get_element(element_kind::Container, None, 0, 0, 300, 300),
+        get_element(element_kind::Image, None, 10, 10, 100, 100),
+        get_element(element_kind::Image, None, 60, 60, 100, 100),
+        // Same box as the first image, so not inside it either
+        get_element(element_kind::Icon, None, 10, 10, 100, 100),
+    ];
+    build_hierarchy(&mut elements);
+    let parents: Vec<Option<usize>> = elements.iter().map(|element| element.parent).collect();
+    assert_eq!(parents, vec![None, Some(0), Some(0), Some(0)]);
+    assert_eq!(elements[0].children, vec![1, 3, 2]);
+    assert!(elements[1].children.is_empty() && elements[2].children.is_empty());
+
+    // Building again gives the same hierarchy rather than adding to it
+    build_hierarchy(&mut elements);
+    assert_eq!(elements[0].children, vec![1, 3, 2]);
+}
+
+#[test]
+fn descriptions_name_each_ancestor() {
+    let mut elements = get_dialog();
+    build_hierarchy(&mut elements);
+    assert_eq!(describe_element(&elements, 2), "container \"Save changes\"");
+    assert_eq!(describe_element(&elements, 4), "button \"OK\" inside container \"Save changes\"");
+    assert_eq!(
+        describe_element(&elements, 1),
+        "text label \"OK\" inside button \"OK\" inside container \"Save changes\""
+    );
+
+    // Unnamed elements are described by kind alone
+    let mut elements = vec![
+        get_element(element_kind::Container, None, 0, 0, 100, 100),
+        get_element(element_kind::Checkbox, Some(""), 10, 10, 12, 12),
+    ];
+    build_hierarchy(&mut elements);
+    assert_eq!(describe_element(&elements, 1), "checkbox inside container");
+}
@@ -0,0 +1,161 @@
+use nea_element_detection::activation::activation_map;
+use nea_element_detection::lines::{
+    connectivity, get_hysteresis_lines, get_lines, get_lines_stats, get_surrounding_pixels, label_components,
+    sanitise_lines, ACTIVATION_THRESHOLD, AREA_THRESHOLD, LARGER_WIDTH_THRESHOLD
+};
+
+const THRESHOLD: f32 = 26.0; // Splits the fixture into many lines
+
+fn get_map(width: u32, height: u32, activated: &[(u32, u32)]) -> activation_map {
+    let mut map = activation_map::new(width, height);
+    for (x, y) in activated {
+        map.set(*x, *y, 50.0);
+    }
+    return map;
+}
+
+// Pseudo-random activations with blobs of every size, some touching only diagonally
+fn get_fixture() -> activation_map {
+    let mut map = activation_map::new(61, 47);
+    for y in 0..47u32 {
+        for x in 0..61u32 {
+            let value = x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503);
+            map.set(x, y, (value % 31) as f32);
+        }
+    }
+    return map;
+}
+
+// The flood fill get_lines used before union-find labelling
+fn flood_fill_lines(activation_map: &activation_map, threshold: f32) -> Vec<Vec<(u32, u32)>> {
+    let (width, height) = (activation_map.width(), activation_map.height());
+    let mut is_visited = vec![false; width as usize * height as usize];
+    let mut lines: Vec<Vec<(u32, u32)>> = Vec::new();
+    for x in 0..width {
+        for y in 0..height {
+            if is_visited[(y * width + x) as usize] || activation_map.get(x, y) < threshold {
+                continue
+            }
+            is_visited[(y * width + x) as usize] = true;
+            let mut line = vec![(x, y)];
+            let mut to_check = vec![(x, y)];
+            while let Some((check_x, check_y)) = to_check.pop() {
+                for (neighbour_x, neighbour_y) in get_surrounding_pixels(check_x, check_y, width, height) {
+                    let index = (neighbour_y * width + neighbour_x) as usize;
+                    if !is_visited[index] && activation_map.get(neighbour_x, neighbour_y) >= threshold {
+                        is_visited[index] = true;
+                        line.push((neighbour_x, neighbour_y));
+                        to_check.push((neighbour_x, neighbour_y));
+                    }
+                }
+            }
+            if line.len() > 4 {
+                lines.push(line);
+            }
+        }
+    }
+    return lines;
+}
+
+fn sorted(lines: Vec<Vec<(u32, u32)>>) -> Vec<Vec<(u32, u32)>> {
+    return lines.into_iter()
+        .map(|mut line| {
+            line.sort();
+            line
+        })
+        .collect();
+}
+
+#[test]
+fn activation_map_is_left_alone() {
+    let map = get_fixture();
+    let before = map.clone();
+    let lines = get_lines(&map, THRESHOLD);
+    assert!(!lines.is_empty());
+    assert_eq!(map, before);
+}
+
+#[test]
+fn diagonal_pixels_join_with_eight_connectivity() {
+    // A staircase, every pixel touching the next only at a corner
+    let staircase: Vec<(u32, u32)> = (0..6).map(|i| (i, i)).collect();
+    let map = get_map(8, 8, &staircase);
+    let (_, eight) = label_components(&map, |_, _| THRESHOLD, |_, _| THRESHOLD, connectivity::Eight);
+    assert_eq!(eight, vec![staircase]);
+    // Single pixels, all too small to keep
+    let (_, four) = label_components(&map, |_, _| THRESHOLD, |_, _| THRESHOLD, connectivity::Four);
+    assert!(four.is_empty());
+}
+
+#[test]
+fn tiny_components_are_dropped() {
+    // Four pixels, then five
+    let map = get_map(10, 10, &[(0, 0), (0, 1), (0, 2), (0, 3), (5, 0), (5, 1), (5, 2), (5, 3), (5, 4)]);
+    let (labels, lines) = label_components(&map, |_, _| THRESHOLD, |_, _| THRESHOLD, connectivity::Eight);
+    assert_eq!(lines, vec![vec![(5, 0), (5, 1), (5, 2), (5, 3), (5, 4)]]);
+    assert_eq!(labels.get(0, 0), None);
+    assert_eq!(labels.get(5, 0), Some(0));
+}
+
+#[test]
+fn labels_match_components() {
+    let map = get_fixture();
+    let (labels, lines) = label_components(&map, |_, _| THRESHOLD, |_, _| THRESHOLD, connectivity::Eight);
+    assert_eq!((labels.width(), labels.height()), (map.width(), map.height()));
+    let mut expected: Vec<Option<usize>> = vec![None; map.width() as usize * map.height() as usize];
+    for (i, line) in lines.iter().enumerate() {
+        for (x, y) in line {
+            assert_eq!(expected[(y * map.width() + x) as usize], None, "pixel in two lines");
+            expected[(y * map.width() + x) as usize] = Some(i);
+        }
+    }
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            assert_eq!(labels.get(x, y), expected[(y * map.width() + x) as usize], "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn matches_flood_fill() {
+    let map = get_fixture();
+    for threshold in [0.0, 26.0, 27.0, 28.0] {
+        let lines = get_lines(&map, threshold);
+        assert!(!lines.is_empty());
+        // Same lines in the same order, only the order of pixels within each differs
+        assert_eq!(sorted(lines), sorted(flood_fill_lines(&map, threshold)), "threshold {}", threshold);
+    }
+}
+
+#[test]
+fn hysteresis_keeps_weak_pixels_joined_to_strong_ones() {
+    let mut map = activation_map::new(20, 5);
+    // A strong seed with a weak run growing out of it
+    for x in 0..3 {
+        map.set(x, 1, 40.0);
+    }
+    for x in 3..9 {
+        map.set(x, 1, 12.0);
+    }
+    // The same weak activation, not touching anything strong
+    for x in 12..18 {
+        map.set(x, 3, 12.0);
+    }
+    let lines = get_hysteresis_lines(&map, 30.0, 10.0);
+    assert_eq!(lines, vec![(0..9).map(|x| (x, 1)).collect::<Vec<(u32, u32)>>()]);
+    // Without hysteresis the seed alone is too small to keep
+    assert!(get_hysteresis_lines(&map, 30.0, 30.0).is_empty());
+    // With the grow threshold as the seed threshold both runs are kept
+    assert_eq!(get_hysteresis_lines(&map, 10.0, 10.0).len(), 2);
+}
+
+#[test]
+fn larger_width_counts_from_the_first_pixel_to_the_last() {
+    // Solid one pixel tall runs, so only their length decides whether they're kept
+    let runs: Vec<Vec<(u32, u32)>> = [LARGER_WIDTH_THRESHOLD, LARGER_WIDTH_THRESHOLD + 1].iter()
+        .map(|length| (0..*length).map(|x| (x, 0)).collect())
+        .collect();
+    let kept = sanitise_lines(get_lines_stats(runs), AREA_THRESHOLD, LARGER_WIDTH_THRESHOLD, ACTIVATION_THRESHOLD);
+    let widths: Vec<u32> = kept.iter().map(|line| line.bounds.width).collect();
+    assert_eq!(widths, vec![LARGER_WIDTH_THRESHOLD + 1]);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use nea_element_detection::lines::line;
+use nea_element_detection::nms::{duplicate_preference, is_duplicate, suppress_duplicates};
+use nea_element_detection::rect;
+use common::get_outline;
+
+// A button's border, its focus ring 2px outside it, and an unrelated label
+fn get_button_lines() -> Vec<line> {
+    return vec![
+        get_outline(12, 12, 80, 24),
+        get_outline(10, 10, 84, 28),
+        get_outline(200, 12, 40, 10),
+    ];
+}
+
+fn get_bounds(lines: &[line]) -> Vec<rect> {
+    return lines.iter().map(|line| line.bounds).collect();
+}
+
+#[test]
+fn nested_boxes_close_together_are_duplicates() {
+    let (outer, inner) = (rect::new(0, 0, 20, 20), rect::new(2, 2, 16, 16));
+    // IoU is only 0.64, but every side is 2px apart
+    assert!(is_duplicate(&outer, &inner, 0.9, 2));
+    assert!(!is_duplicate(&outer, &inner, 0.9, 1));
+    assert!(!is_duplicate(&outer, &rect::new(30, 0, 20, 20), 0.5, 2));
+}
+
+#[test]
+fn preference_chooses_which_duplicate_is_kept() {
+    let outer = suppress_duplicates(get_button_lines(), 0.6, 2, duplicate_preference::Outer);
+    assert_eq!(get_bounds(&outer), vec![rect::new(10, 10, 84, 28), rect::new(200, 12, 40, 10)]);
+
+    let inner = suppress_duplicates(get_button_lines(), 0.6, 2, duplicate_preference::Inner);
+    assert_eq!(get_bounds(&inner), vec![rect::new(12, 12, 80, 24), rect::new(200, 12, 40, 10)]);
+
+    let merged = suppress_duplicates(get_button_lines(), 0.6, 2, duplicate_preference::Merge);
+    assert_eq!(get_bounds(&merged), vec![rect::new(10, 10, 84, 28), rect::new(200, 12, 40, 10)]);
+    let pixel_count = get_button_lines()[0].pixels.len() + get_button_lines()[1].pixels.len();
+    assert_eq!(merged[0].pixels.len(), pixel_count);
+}
+
+#[test]
+fn thresholds_above_the_overlap_keep_both() {
+    let lines = suppress_duplicates(get_button_lines(), 0.95, 1, duplicate_preference::Outer);
+    assert_eq!(lines.len(), 3);
+}
@@ -0,0 +1,57 @@
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::{Rgb, RgbImage};
+use nea_element_detection::ocr::{glyph_recognizer, text_recognizer};
+
+const FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+// Dark text on a light background with a margin all round, anti-aliased like a screenshot
+fn render_text(text: &str, size: f32) -> RgbImage {
+    let font = FontRef::try_from_slice(FONT).unwrap();
+    let scaled_font = font.as_scaled(PxScale::from(size));
+    let width: f32 = text.chars().map(|character| scaled_font.h_advance(scaled_font.glyph_id(character))).sum();
+    let margin = 6.0;
+    let mut image = RgbImage::from_pixel(
+        (width + 2.0 * margin).ceil() as u32,
+        (scaled_font.height() + 2.0 * margin).ceil() as u32,
+        Rgb([245, 245, 245])
+    );
+    let mut caret = margin;
+    for character in text.chars() {
+        let glyph_id = scaled_font.glyph_id(character);
+        let glyph = glyph_id.with_scale_and_position(PxScale::from(size), ab_glyph::point(caret, margin + scaled_font.ascent()));
+        caret += scaled_font.h_advance(glyph_id);
+        let Some(outline) = scaled_font.outline_glyph(glyph) else { continue };
+        let bounds = outline.px_bounds();
+        outline.draw(|x, y, coverage| {
+            let pixel = image.get_pixel_mut(bounds.min.x as u32 + x, bounds.min.y as u32 + y);
+            let value = (245.0 * (1.0 - coverage) + 20.0 * coverage).round() as u8;
+            *pixel = Rgb([value, value, value]);
+        });
+    }
+    return image;
+}
+
+#[test]
+fn reads_rendered_text() {
+    let recognizer = glyph_recognizer::default();
+    // Sizes between the training sizes too. Letters which touch (e.g. "fo" at 13px) are
+    // read as one glyph, so aren't used here.
+    for (text, size) in [("Submit form", 16.0), ("Help 42", 16.0), ("Next page", 20.0), ("Save", 14.0)] {
+        let image = render_text(text, size);
+        let words = recognizer.recognize_words(&image).unwrap();
+        let texts: Vec<&str> = words.iter().map(|word| word.text.as_str()).collect();
+        assert_eq!(texts, text.split(' ').collect::<Vec<&str>>(), "size {}", size);
+        // Word boxes are in the image and in reading order
+        assert!(words.windows(2).all(|pair| pair[0].x + pair[0].width < pair[1].x));
+        assert!(words.iter().all(|word| word.x + word.width <= image.width() && word.confidence > 0.5));
+        assert_eq!(recognizer.recognize(&image).unwrap(), text);
+    }
+}
+
+#[test]
+fn reads_nothing_from_blank_images() {
+    let recognizer = glyph_recognizer::default();
+    let image = RgbImage::from_pixel(40, 20, Rgb([255, 255, 255]));
+    assert!(recognizer.recognize_words(&image).unwrap().is_empty());
+    assert_eq!(recognizer.recognize(&image).unwrap(), "");
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use image::Rgb;
+use nea_element_detection::frequency::get_layouts;
+use nea_element_detection::hierarchy::build_hierarchy;
+use nea_element_detection::ocr::recognized_word;
+use nea_element_detection::output::{get_elements_output, image_output};
+use nea_element_detection::pipeline::element_text;
+use nea_element_detection::{detector, detector_config, element_kind};
+use common::get_box_image;
+
+fn get_output() -> image_output {
+    let image = get_box_image();
+    let config = detector_config { rotated_bounds: true, ..detector_config::default() };
+    let mut elements = detector::new(config).with_recognizer(None).detect(&image);
+    assert!(!elements.is_empty());
+    // Give one element text, as the detector finds none without a recognizer
+    elements[0].kind = element_kind::Button;
+    elements[0].text = Some(element_text {
+        stroke_color: Rgb([30, 30, 30]),
+        text: "OK".to_string(),
+        words: vec![recognized_word { text: "OK".to_string(), x: 22, y: 24, width: 12, height: 10, confidence: 0.9 }],
+    });
+    build_hierarchy(&mut elements);
+    return image_output {
+        path: "screenshot.png".to_string(),
+        width: image.width(),
+        height: image.height(),
+        elements: get_elements_output(&elements),
+        layouts: Some(get_layouts(&image, &elements)),
+    };
+}
+
+fn get_keys(value: &serde_json::Value) -> Vec<&str> {
+    let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(|key| key.as_str()).collect();
+    keys.sort();
+    return keys;
+}
+
+#[test]
+fn image_output_round_trips_through_json() {
+    let output = get_output();
+    let json = serde_json::to_string_pretty(&output).unwrap();
+    let parsed: image_output = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, output);
+}
+
+#[test]
+fn json_has_the_documented_fields() {
+    let value = serde_json::to_value(get_output()).unwrap();
+    assert_eq!(get_keys(&value), vec!["elements", "height", "layouts", "path", "width"]);
+    let element = &value["elements"][0];
+    assert_eq!(get_keys(element), vec![
+        "area", "bottom_left", "bottom_right", "bounds", "children", "confidence", "description",
+        "dominant_color", "fill_ratio", "hull", "id", "kind", "label", "parent", "pixel_count",
+        "rotated_bounds", "text", "top_left", "top_right",
+    ]);
+    assert_eq!(get_keys(&element["bounds"]), vec!["height", "width", "x", "y"]);
+    assert_eq!(get_keys(&element["rotated_bounds"]), vec!["angle", "center", "height", "width"]);
+    assert_eq!(get_keys(&element["text"]), vec!["stroke_color", "text", "words"]);
+    assert_eq!(get_keys(&element["text"]["words"][0]), vec!["confidence", "height", "text", "width", "x", "y"]);
+    // Kinds are snake case, points and colours are arrays
+    assert_eq!(element["kind"], "button");
+    assert_eq!(element["dominant_color"].as_array().unwrap().len(), 3);
+    assert_eq!(element["top_left"].as_array().unwrap().len(), 2);
+    assert!(element["description"].as_str().unwrap().starts_with("button \"OK\""));
+}
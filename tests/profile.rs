@@ -0,0 +1,81 @@
+mod common;
+
+use std::path::Path;
+use image::{Rgb, RgbImage};
+use nea_element_detection::pipeline::config_error;
+use nea_element_detection::profile::{config_profile, CONFIG_PROFILES};
+use nea_element_detection::threshold::threshold_mode;
+use nea_element_detection::{detector, detector_config};
+use common::{get_box_image, temp_directory};
+
+fn load(path: &Path, contents: &str) -> Result<detector_config, config_error> {
+    std::fs::write(path, contents).unwrap();
+    return detector_config::load(path);
+}
+
+#[test]
+fn profiles_are_valid_and_named() {
+    for profile in CONFIG_PROFILES {
+        assert!(profile.get_config().validate().is_ok(), "{}", profile);
+        assert_eq!(profile.get_name().parse::<config_profile>(), Ok(profile));
+    }
+    assert_eq!(config_profile::from_name("Dark Mobile"), Some(config_profile::DarkMobile));
+    assert_eq!(config_profile::Default.get_config(), detector_config::default());
+}
+
+#[test]
+fn loads_toml_on_top_of_profile() {
+    let directory = temp_directory::new("profile_toml");
+    let path = directory.join("config.toml");
+    let config = load(&path, "profile = \"photo of screen\"\nline_threshold = 20.0\n");
+    let saved_path = directory.join("saved.toml");
+    let original = detector_config { area_threshold: 5, ..config_profile::DarkMobile.get_config() };
+    original.save(&saved_path).unwrap();
+    let saved = detector_config::load(&saved_path);
+
+    let config = config.unwrap();
+    assert_eq!(config.line_threshold, 20.0);
+    assert_eq!(config.threshold_mode, threshold_mode::Adaptive);
+    assert_eq!(config, detector_config { line_threshold: 20.0, ..config_profile::PhotoOfScreen.get_config() });
+    assert_eq!(saved.unwrap(), original);
+}
+
+#[test]
+fn rejects_out_of_range_and_unknown_settings() {
+    let directory = temp_directory::new("profile_invalid");
+    let path = directory.join("config.toml");
+    let out_of_range = load(&path, "pyramid_scale = 1.5\n");
+    let canny_order = load(&path, "canny_low_threshold = 40.0\ncanny_high_threshold = 20.0\n");
+    let not_a_number = load(&path, "hysteresis_ratio = nan\n");
+    let misspelt = load(&path, "pyramid_scal = 0.5\n");
+    let unknown_profile = load(&path, "profile = \"tablet\"\n");
+
+    let Err(config_error::OutOfRange(message)) = out_of_range else { panic!("{:?}", out_of_range) };
+    assert!(message.contains("pyramid_scale is 1.5"), "{}", message);
+    assert!(matches!(canny_order, Err(config_error::OutOfRange(message)) if message.contains("canny_high_threshold")));
+    assert!(not_a_number.is_err());
+    let not_a_number = detector_config { hysteresis_ratio: f32::NAN, ..detector_config::default() };
+    assert!(matches!(not_a_number.validate(), Err(config_error::OutOfRange(_))));
+    assert!(matches!(misspelt, Err(config_error::Invalid(_))));
+    assert!(matches!(unknown_profile, Err(config_error::Invalid(message)) if message.contains("tablet")));
+}
+
+#[test]
+fn profiles_find_nothing_in_a_blank_image() {
+    for color in [Rgb([250, 250, 250]), Rgb([18, 18, 18])] {
+        let image = RgbImage::from_pixel(64, 48, color);
+        for profile in CONFIG_PROFILES {
+            let elements = detector::new(profile.get_config()).with_recognizer(None).detect(&image);
+            assert!(elements.is_empty(), "{} found {} elements", profile, elements.len());
+        }
+    }
+}
+
+#[test]
+fn profiles_find_a_box() {
+    let image = get_box_image();
+    for profile in CONFIG_PROFILES {
+        let elements = detector::new(profile.get_config()).with_recognizer(None).detect(&image);
+        assert_eq!(elements.len(), 1, "{}", profile);
+    }
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use image::RgbImage;
+use nea_element_detection::pyramid::{get_pyramid, merge_pyramid_lines, scale_line_pixels};
+use nea_element_detection::rect;
+use common::{get_line, get_outline, get_outline_pixels};
+
+#[test]
+fn pyramid_halves_until_too_small() {
+    let pyramid = get_pyramid(&RgbImage::new(100, 70), 5, 0.5);
+    let sizes: Vec<(u32, u32)> = pyramid.iter().map(|level| level.dimensions()).collect();
+    // 13x9 would be below the minimum size
+    assert_eq!(sizes, vec![(100, 70), (50, 35), (25, 18)]);
+    assert_eq!(get_pyramid(&RgbImage::new(100, 70), 3, 1.5).len(), 1);
+}
+
+#[test]
+fn scaled_pixels_cross_their_blocks() {
+    let scaled = scale_line_pixels(&[(1, 0), (2, 1)], (3, 2), (9, 5));
+    // x blocks are 3 wide, y blocks are rows 0 - 1 and 2 - 4, each crossed corner to corner
+    assert_eq!(scaled, vec![(3, 0), (4, 0), (5, 1), (6, 2), (7, 3), (8, 4)]);
+    assert_eq!(rect::from_points(&scaled), Some(rect::new(3, 0, 6, 5)));
+}
+
+#[test]
+fn scaled_lines_keep_full_size_density() {
+    // The same outline found at half size and at full size
+    let coarse = get_line(scale_line_pixels(&get_outline_pixels(5, 5, 40, 30), (50, 50), (100, 100)));
+    let fine = get_outline(10, 10, 80, 60);
+    assert_eq!(coarse.bounds, fine.bounds);
+    assert!((coarse.get_activation() - fine.get_activation()).abs() < 0.002,
+        "{} != {}", coarse.get_activation(), fine.get_activation());
+    // Each level pixel becomes 1 / scale pixels, not 1 / scale squared
+    assert_eq!(scale_line_pixels(&[(0, 0)], (10, 10), (40, 40)), vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+}
+
+#[test]
+fn finer_lines_suppress_overlapping_coarser_ones() {
+    let fine = vec![get_line(vec![(0, 0), (9, 9)]), get_line(vec![(2, 2), (8, 8)])];
+    let coarse = vec![get_line(vec![(0, 0), (9, 10)]), get_line(vec![(20, 20), (30, 30)])];
+    let merged = merge_pyramid_lines(vec![fine, coarse], 0.5);
+    let bounds: Vec<rect> = merged.iter().map(|line| line.bounds).collect();
+    // Overlapping lines within a level are both kept
+    assert_eq!(bounds, vec![rect::new(0, 0, 10, 10), rect::new(2, 2, 7, 7), rect::new(20, 20, 11, 11)]);
+}
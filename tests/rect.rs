@@ -0,0 +1,119 @@
+mod common;
+
+use image::{Rgb, RgbImage};
+use nea_element_detection::draw::{draw_bounding_box, get_line_image, get_line_images};
+use nea_element_detection::rect;
+use common::get_line;
+
+const BOX_COLOR: Rgb<u8> = Rgb([0, 255, 0]);
+
+// Every pixel gets a unique colour so crops can be checked against their source position
+fn get_gradient_image(width: u32, height: u32) -> RgbImage {
+    return RgbImage::from_fn(width, height, |x, y| Rgb([x as u8, y as u8, 7]));
+}
+
+#[test]
+fn from_points_covers_every_point() {
+    let rect = rect::from_points(&[(5, 9), (2, 4), (7, 6)]).unwrap();
+    assert_eq!(rect, rect::new(2, 4, 6, 6));
+    assert_eq!(rect.right(), 7);
+    assert_eq!(rect.bottom(), 9);
+    assert_eq!(rect.top_left(), (2, 4));
+    assert_eq!(rect.bottom_right(), (7, 9));
+    assert_eq!(rect.area(), 36);
+    assert_eq!(rect::from_points(&[]), None);
+}
+
+#[test]
+fn single_pixel_is_one_by_one() {
+    let rect = rect::from_points(&[(3, 3)]).unwrap();
+    assert_eq!((rect.width, rect.height, rect.area()), (1, 1, 1));
+    assert!(rect.contains_point((3, 3)));
+    assert!(!rect.contains_point((4, 3)));
+}
+
+#[test]
+fn intersection_union_and_iou() {
+    let a = rect::new(0, 0, 4, 4);
+    let b = rect::new(2, 2, 4, 4);
+    assert_eq!(a.intersection(&b), Some(rect::new(2, 2, 2, 2)));
+    assert_eq!(a.union(&b), rect::new(0, 0, 6, 6));
+    assert!((a.get_iou(&b) - 4.0 / 28.0).abs() < 1e-6);
+    assert_eq!(a.get_iou(&a), 1.0);
+    assert_eq!(a.intersection(&rect::new(4, 0, 2, 2)), None);
+    assert_eq!(a.get_iou(&rect::new(4, 0, 2, 2)), 0.0);
+    assert!(a.contains(&rect::new(1, 1, 3, 3)));
+    assert!(!a.contains(&b));
+}
+
+#[test]
+fn zero_sizes_are_rejected_when_deserializing() {
+    let rect: rect = serde_json::from_str(r#"{"x": 3, "y": 4, "width": 1, "height": 7}"#).unwrap();
+    assert_eq!((rect.width, rect.height, rect.right(), rect.bottom()), (1, 7, 3, 10));
+    assert_eq!(serde_json::from_str::<rect>(&serde_json::to_string(&rect).unwrap()).unwrap(), rect);
+    let error = serde_json::from_str::<rect>(r#"{"x": 3, "y": 4, "width": 0, "height": 7}"#).unwrap_err();
+    assert!(error.to_string().contains("0x7"), "{}", error);
+    assert!(serde_json::from_str::<rect>(r#"{"x": 3, "y": 4, "width": 0}"#).is_err());
+}
+
+#[test]
+#[should_panic(expected = "expected at least 1x1")]
+fn new_rejects_zero_sizes() {
+    rect::new(3, 4, 0, 5);
+}
+
+#[test]
+fn line_bounds_use_downwards_y() {
+    let line = get_line(vec![(10, 20), (14, 20), (10, 23), (14, 23)]);
+    assert_eq!(line.bounds, rect::new(10, 20, 5, 4));
+    assert_eq!(line.get_activation(), 4.0 / 20.0);
+}
+
+#[test]
+fn crop_matches_drawn_box() {
+    let image = get_gradient_image(40, 30);
+    let line = get_line(vec![(6, 4), (17, 9), (11, 12), (8, 7)]);
+    let crop = get_line_image(&line, &image);
+    assert_eq!(crop.dimensions(), (line.bounds.width, line.bounds.height));
+    assert_eq!(*crop.get_pixel(0, 0), *image.get_pixel(line.bounds.x, line.bounds.y));
+
+    let drawn = draw_bounding_box(image.clone(), &line);
+    for (x, y, pixel) in drawn.enumerate_pixels() {
+        let inside_crop = line.bounds.contains_point((x, y));
+        let on_border = inside_crop
+            && (x == line.bounds.x || x == line.bounds.right() || y == line.bounds.y || y == line.bounds.bottom());
+        if on_border {
+            assert_eq!(*pixel, BOX_COLOR, "box missing at ({}, {})", x, y);
+        } else {
+            assert_eq!(*pixel, *image.get_pixel(x, y), "box drawn outside the crop at ({}, {})", x, y);
+        }
+        if inside_crop {
+            let crop_pixel = crop.get_pixel(x - line.bounds.x, y - line.bounds.y);
+            assert_eq!(*crop_pixel, *image.get_pixel(x, y));
+        }
+    }
+
+    // Cropping the drawn image gives the box as the crop's outer border
+    let drawn_crop = get_line_image(&line, &drawn);
+    let (width, height) = drawn_crop.dimensions();
+    for x in 0..width {
+        assert_eq!(*drawn_crop.get_pixel(x, 0), BOX_COLOR);
+        assert_eq!(*drawn_crop.get_pixel(x, height - 1), BOX_COLOR);
+    }
+    for y in 0..height {
+        assert_eq!(*drawn_crop.get_pixel(0, y), BOX_COLOR);
+        assert_eq!(*drawn_crop.get_pixel(width - 1, y), BOX_COLOR);
+    }
+}
+
+#[test]
+fn boxes_touching_the_image_edge() {
+    let image = get_gradient_image(12, 8);
+    let line = get_line(vec![(0, 0), (11, 7)]);
+    let crops = get_line_images(&[line], &image);
+    assert_eq!(crops[0], image);
+    let line = get_line(vec![(0, 0), (11, 7)]);
+    let drawn = draw_bounding_box(image, &line);
+    assert_eq!(*drawn.get_pixel(11, 7), BOX_COLOR);
+    assert_eq!(*drawn.get_pixel(0, 7), BOX_COLOR);
+}
@@ -0,0 +1,72 @@
+use std::process::Command;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use nea_element_detection::synthetic::{generate_screenshot, synthetic_settings};
+use nea_element_detection::{element_kind, rect};
+
+#[test]
+fn same_seed_gives_same_screenshot() {
+    let settings = synthetic_settings::default();
+    let (first, first_elements) = generate_screenshot(&mut StdRng::seed_from_u64(7), &settings);
+    let (second, second_elements) = generate_screenshot(&mut StdRng::seed_from_u64(7), &settings);
+    assert!(first == second);
+    assert_eq!(first_elements, second_elements);
+}
+
+#[test]
+fn elements_are_inside_the_image_and_apart() {
+    let settings = synthetic_settings { width: 400, height: 300, ..synthetic_settings::default() };
+    for seed in 0..10 {
+        let (buffer, elements) = generate_screenshot(&mut StdRng::seed_from_u64(seed), &settings);
+        assert_eq!(buffer.dimensions(), (400, 300));
+        assert!(!elements.is_empty());
+        let image = rect::new(0, 0, 400, 300);
+        for element in &elements {
+            assert!(image.contains(&element.bounds));
+            assert_eq!(element.text.is_some(), element.kind == element_kind::TextLabel);
+        }
+        // Text is drawn inside buttons and fields, but nothing else overlaps
+        let outer: Vec<rect> = elements.iter()
+            .filter(|element| element.kind != element_kind::TextLabel)
+            .map(|element| element.bounds)
+            .collect();
+        for (i, a) in outer.iter().enumerate() {
+            for b in &outer[i + 1..] {
+                assert!(a.intersection(b).is_none(), "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn settings_which_would_panic_are_rejected() {
+    let default = synthetic_settings::default();
+    assert_eq!(default.validate(), Ok(()));
+    let invalid = [
+        synthetic_settings { width: 0, ..default },
+        synthetic_settings { font_size_mean: -4.0, ..default },
+        synthetic_settings { font_size_std: f32::NAN, ..default },
+        synthetic_settings { font_size_std: f32::INFINITY, ..default },
+        synthetic_settings { max_noise_std: -1.0, ..default },
+        synthetic_settings { max_noise_std: f32::NAN, ..default },
+        synthetic_settings { dark_theme_chance: 1.5, ..default },
+    ];
+    for settings in invalid {
+        assert!(settings.validate().is_err(), "{:?}", settings);
+    }
+}
+
+#[test]
+fn generator_reports_invalid_settings() {
+    let directory = std::env::temp_dir().join(format!("nea_synthetic_invalid_{}", std::process::id()));
+    for arg in ["--max-noise-std=-1", "--font-size-std=inf", "--dark-theme-chance=2"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_synthetic"))
+            .args(["--output-dir", directory.to_str().unwrap(), "--count", "1"])
+            .arg(arg)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{}", arg);
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error: "), "{}", arg);
+    }
+    assert!(!directory.exists());
+}
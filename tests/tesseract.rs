@@ -0,0 +1,60 @@
+#![cfg(feature = "tesseract")]
+
+use nea_element_detection::ocr::ocr_error;
+use nea_element_detection::ocr::tesseract::{parse_tsv, tesseract_recognizer};
+
+const HEADER: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext";
+
+#[test]
+fn parses_word_rows() {
+    let tsv = format!("{}\n{}\n{}\n{}\n{}\n", HEADER,
+        "1\t1\t0\t0\t0\t0\t0\t0\t200\t40\t-1\t",
+        "4\t1\t1\t1\t1\t0\t4\t6\t150\t30\t-1\t",
+        "5\t1\t1\t1\t1\t1\t4\t6\t80\t30\t96.5\tSubmit",
+        "5\t1\t1\t1\t1\t2\t100\t8\t54\t28\t88\tform",
+    );
+    let words = parse_tsv(&tsv, 2.0).unwrap();
+    assert_eq!(words.len(), 2);
+    assert_eq!(words[0].text, "Submit");
+    // Boxes are scaled back down to the original image
+    assert_eq!((words[0].x, words[0].y, words[0].width, words[0].height), (2, 3, 40, 15));
+    assert!((words[0].confidence - 0.965).abs() < 1e-6);
+    assert_eq!((words[1].text.as_str(), words[1].x, words[1].confidence), ("form", 50, 0.88));
+}
+
+#[test]
+fn skips_empty_and_short_rows() {
+    let tsv = format!("{}\n{}\n{}\n{}\n{}\n", HEADER,
+        "5\t1\t1\t1\t1\t1\t4\t6\t80\t30\t95\t ",
+        // Cut off before conf and text
+        "5\t1\t1\t1\t1\t2\t4\t6",
+        "5",
+        "5\t1\t1\t1\t1\t3\t4\t6\t80\t30\t91\tOK",
+    );
+    let words = parse_tsv(&tsv, 1.0).unwrap();
+    assert_eq!(words.iter().map(|word| word.text.as_str()).collect::<Vec<&str>>(), vec!["OK"]);
+}
+
+#[test]
+fn short_rows_are_skipped_whatever_the_column_order() {
+    // text before conf, so a row long enough for text can still be missing conf
+    let header = "text\tlevel\tleft\ttop\twidth\theight\tconf";
+    let tsv = format!("{}\n{}\n{}\n", header, "Save\t5\t1\t2\t3", "Save\t5\t1\t2\t3\t4\t90");
+    let words = parse_tsv(&tsv, 1.0).unwrap();
+    assert_eq!(words.len(), 1);
+    assert_eq!((words[0].x, words[0].y, words[0].width, words[0].height), (1, 2, 3, 4));
+}
+
+#[test]
+fn rejects_bad_output() {
+    assert!(parse_tsv("level\tleft\ttop\n5\t1\t2\n", 1.0).is_err());
+    let tsv = format!("{}\n{}\n", HEADER, "5\t1\t1\t1\t1\t1\tx\t6\t80\t30\t95\tOK");
+    assert!(parse_tsv(&tsv, 1.0).is_err());
+}
+
+#[test]
+fn missing_binary_is_reported_up_front() {
+    let result = tesseract_recognizer::with_binary("definitely-not-tesseract");
+    let Err(ocr_error::EngineMissing(message)) = result else { panic!("expected EngineMissing") };
+    assert!(message.contains("definitely-not-tesseract not found"), "{}", message);
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use image::{Rgb, RgbImage};
+use nea_element_detection::activation::{activation_map, get_activation_stats, get_histogram_bin, HISTOGRAM_BINS};
+use nea_element_detection::lines::get_lines;
+use nea_element_detection::threshold::{
+    get_local_thresholds, get_mean_std_threshold, get_otsu_threshold, get_percentile_threshold, threshold_mode,
+    THRESHOLD_MODES
+};
+use nea_element_detection::{detector, detector_config, rect};
+use common::get_box_image;
+
+// values along the top row of a map which is otherwise unactivated
+fn get_map(values: &[f32], height: u32) -> activation_map {
+    let mut map = activation_map::new(values.len() as u32, height);
+    for (x, value) in values.iter().enumerate() {
+        map.set(x as u32, 0, *value);
+    }
+    return map;
+}
+
+#[test]
+fn otsu_splits_two_modes() {
+    let mut histogram = vec![0; HISTOGRAM_BINS];
+    histogram[20] = 50;
+    histogram[21] = 30;
+    histogram[200] = 40;
+    histogram[203] = 10;
+    let threshold = get_otsu_threshold(&histogram);
+    assert!(get_histogram_bin(threshold) > 21 && get_histogram_bin(threshold) <= 200, "{}", threshold);
+}
+
+#[test]
+fn otsu_ignores_unactivated_pixels() {
+    // Mostly empty, with faint and strong activation. Counting the empty pixels would
+    // split them from everything else.
+    let mut values = vec![15.0; 40];
+    values.extend(vec![180.0; 20]);
+    let map = get_map(&values, 50);
+    let threshold = get_otsu_threshold(&get_activation_stats(&map).histogram);
+    assert!(threshold > 15.0 && threshold <= 180.0, "{}", threshold);
+}
+
+#[test]
+fn percentile_of_activated_pixels() {
+    let values: Vec<f32> = (1..=100).map(|value| value as f32).collect();
+    let map = get_map(&values, 3);
+    assert_eq!(get_percentile_threshold(&map, 0.0), 1.0);
+    assert_eq!(get_percentile_threshold(&map, 75.0), 75.0);
+    assert_eq!(get_percentile_threshold(&map, 100.0), 100.0);
+    assert_eq!(get_percentile_threshold(&map, 150.0), 100.0);
+}
+
+#[test]
+fn stats_and_mean_std_of_activated_pixels() {
+    // Mean 5, standard deviation 2
+    let map = get_map(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0], 2);
+    let stats = get_activation_stats(&map);
+    assert_eq!(stats.activation_count, 8);
+    assert_eq!((stats.avg_activation, stats.std_activation, stats.median_activation), (5.0, 2.0, 4.5));
+    assert_eq!((stats.min, stats.max), (0.0, 9.0));
+    assert_eq!(stats.histogram.len(), HISTOGRAM_BINS);
+    assert_eq!(stats.histogram.iter().sum::<u32>(), 8);
+    assert_eq!(stats.histogram[get_histogram_bin(4.0)], 3);
+    assert_eq!(get_mean_std_threshold(&stats, 0.5), 6.0);
+    assert_eq!(get_mean_std_threshold(&stats, 0.0), 5.0);
+}
+
+#[test]
+fn empty_maps_give_infinity() {
+    for map in [activation_map::new(10, 10), activation_map::new(0, 0)] {
+        let stats = get_activation_stats(&map);
+        assert_eq!(stats.activation_count, 0);
+        assert_eq!(get_otsu_threshold(&stats.histogram), f32::INFINITY);
+        assert_eq!(get_percentile_threshold(&map, 50.0), f32::INFINITY);
+        assert_eq!(get_mean_std_threshold(&stats, 1.0), f32::INFINITY);
+    }
+}
+
+#[test]
+fn automatic_thresholds_find_no_lines_in_blank_maps() {
+    let map = activation_map::new(64, 48);
+    let stats = get_activation_stats(&map);
+    let thresholds = [
+        get_otsu_threshold(&stats.histogram),
+        get_percentile_threshold(&map, 50.0),
+        get_mean_std_threshold(&stats, 0.5),
+    ];
+    for threshold in thresholds {
+        assert!(get_lines(&map, threshold).is_empty(), "{}", threshold);
+    }
+}
+
+// Elements each mode finds in a light screenshot, blank or with one dark box on it
+fn get_mode_bounds(has_box: bool) -> Vec<(threshold_mode, Vec<rect>)> {
+    let image = match has_box {
+        true => get_box_image(),
+        false => RgbImage::from_pixel(80, 60, Rgb([250, 250, 250])),
+    };
+    return THRESHOLD_MODES.into_iter()
+        .map(|mode| {
+            let config = detector_config { threshold_mode: mode, ..detector_config::default() };
+            let elements = detector::new(config).with_recognizer(None).detect(&image);
+            (mode, elements.iter().map(|element| element.line.bounds).collect())
+        })
+        .collect();
+}
+
+#[test]
+fn every_mode_finds_nothing_in_a_blank_image() {
+    for (mode, bounds) in get_mode_bounds(false) {
+        assert!(bounds.is_empty(), "{}: {:?}", mode, bounds);
+    }
+}
+
+#[test]
+fn every_mode_finds_a_box() {
+    // The brush activates two pixels either side of each edge
+    let expected = rect::new(18, 18, 34, 24);
+    for (mode, bounds) in get_mode_bounds(true) {
+        assert_eq!(bounds, vec![expected], "{}", mode);
+    }
+}
+
+#[test]
+fn local_thresholds_are_window_means() {
+    let (width, height, radius, offset) = (23u32, 17u32, 3u32, 2.5);
+    let mut map = activation_map::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            map.set(x, y, ((x * 37 + y * 91) % 50) as f32);
+        }
+    }
+    let thresholds = get_local_thresholds(&map, radius, offset);
+    for y in 0..height {
+        for x in 0..width {
+            // Window clipped to the map
+            let mut total = 0.0;
+            let mut count = 0;
+            for window_y in y.saturating_sub(radius)..(y + radius + 1).min(height) {
+                for window_x in x.saturating_sub(radius)..(x + radius + 1).min(width) {
+                    total += map.get(window_x, window_y);
+                    count += 1;
+                }
+            }
+            let expected = total / count as f32 + offset;
+            assert!((thresholds.get(x, y) - expected).abs() < 1e-3, "({}, {})", x, y);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use nea_element_detection::edges::edge_operator;
+use nea_element_detection::threshold::threshold_mode;
+use nea_element_detection::tune::{
+    get_grid_configs, get_grid_size, get_random_configs, search_space, MAX_GRID_CONFIGS
+};
+use nea_element_detection::detector_config;
+use common::temp_directory;
+
+#[test]
+fn grid_tries_every_combination() {
+    let base = detector_config::default();
+    let space = search_space::default();
+    let configs = get_grid_configs(&base, &space, 2);
+    assert_eq!(configs.len(), 64);
+    let mut line_thresholds: Vec<f32> = configs.iter().map(|config| config.line_threshold).collect();
+    line_thresholds.sort_by(f32::total_cmp);
+    line_thresholds.dedup();
+    assert_eq!(line_thresholds, vec![space.line_threshold.0, space.line_threshold.1]);
+    // Settings outside the search space are left alone
+    assert!(configs.iter().all(|config| config.edge_operator == base.edge_operator));
+
+    // Narrower whole number ranges than steps don't repeat values
+    let narrow = search_space { area_threshold: (4, 5), ..space };
+    let configs = get_grid_configs(&base, &narrow, 3);
+    assert_eq!(configs.len(), 3 * 3 * 2 * 3 * 3 * 3);
+}
+
+#[test]
+fn grid_skips_settings_the_base_config_doesnt_use() {
+    let base = detector_config {
+        threshold_mode: threshold_mode::Otsu,
+        edge_operator: edge_operator::Sobel,
+        ..detector_config::default()
+    };
+    let space = search_space::default();
+    let configs = get_grid_configs(&base, &space, 2);
+    assert_eq!(configs.len(), 16);
+    assert_eq!(get_grid_size(&base, &space, 2), 16);
+    assert!(configs.iter().all(|config| config.line_threshold == base.line_threshold));
+    assert!(configs.iter().all(|config| config.immediate_neighbour_weight == base.immediate_neighbour_weight));
+
+    let configs = get_random_configs(&base, &space, 5, &mut StdRng::seed_from_u64(7));
+    assert!(configs.iter().all(|config| config.line_threshold == base.line_threshold));
+    assert!(configs.iter().all(|config| config.immediate_neighbour_weight == base.immediate_neighbour_weight));
+}
+
+#[test]
+fn grid_size_is_known_before_building() {
+    let base = detector_config::default();
+    let space = search_space::default();
+    assert_eq!(get_grid_size(&base, &space, 3), get_grid_configs(&base, &space, 3).len());
+    assert!(get_grid_size(&base, &space, 5) > MAX_GRID_CONFIGS);
+    // Doesn't overflow
+    assert_eq!(get_grid_size(&base, &space, MAX_GRID_CONFIGS), usize::MAX);
+}
+
+#[test]
+fn random_configs_stay_in_range() {
+    let base = detector_config::default();
+    let space = search_space::default();
+    let configs = get_random_configs(&base, &space, 20, &mut StdRng::seed_from_u64(7));
+    assert_eq!(configs.len(), 20);
+    for config in &configs {
+        assert!((space.line_threshold.0..=space.line_threshold.1).contains(&config.line_threshold));
+        assert!((space.area_threshold.0..=space.area_threshold.1).contains(&config.area_threshold));
+        assert!((space.activation_threshold.0..=space.activation_threshold.1).contains(&config.activation_threshold));
+        assert!((space.difference_color_threshold.0..=space.difference_color_threshold.1)
+            .contains(&config.difference_color_threshold));
+    }
+    // Same seed, same configs
+    assert_eq!(configs, get_random_configs(&base, &space, 20, &mut StdRng::seed_from_u64(7)));
+}
+
+#[test]
+fn configs_save_and_load() {
+    let directory = temp_directory::new("tune_config");
+    let path = directory.join("config.json");
+    let config = detector_config { line_threshold: 12.5, area_threshold: 7, ..detector_config::default() };
+    config.save(&path).unwrap();
+    let loaded = detector_config::load(&path).unwrap();
+
+    // Missing settings take their defaults
+    let partial_path = directory.join("partial.json");
+    std::fs::write(&partial_path, r#"{"area_threshold": 3}"#).unwrap();
+    let partial = detector_config::load(&partial_path).unwrap();
+    std::fs::write(&partial_path, r#"{"area_treshold": 3}"#).unwrap();
+    let misspelt = detector_config::load(&partial_path);
+
+    assert_eq!(loaded, config);
+    assert_eq!(partial, detector_config { area_threshold: 3, ..detector_config::default() });
+    assert!(misspelt.is_err());
+}